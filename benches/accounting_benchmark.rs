@@ -1,11 +1,12 @@
-use rust_accounting::{excel};
+use rust_accounting::excel;
 
 use criterion::{criterion_group, criterion_main, Criterion};
 
-const MOCK_TRANSACTIONS: u32= 100;
+const MOCK_TRANSACTIONS: u32 = 100;
 
 fn create_mock_transactions_benchmark(c: &mut Criterion) -> Result<(), Box<dyn std::error::Error>> {
-    let _  = excel::test_setup::create_mock_transactions(MOCK_TRANSACTIONS).map_err(|e| format!("{:?}", e))?;
+    let _ = excel::test_setup::create_mock_transactions(MOCK_TRANSACTIONS)
+        .map_err(|e| format!("{:?}", e))?;
     Ok(())
 }
 
@@ -20,31 +21,45 @@ fn write_benchmark(c: &mut Criterion) -> Result<(), Box<dyn std::error::Error>>
                 &mock.path,
                 mock.info,
                 &mock.categories,
-                &mock.date_delimiter,
-                &mock.date_month_style,
-                &mock.date_language,
-                &mock.date_capitalize,
+                &excel::writing::WriteOptions {
+                    schema: &excel::schema::Schema::default(),
+                    rates: &mock.rates,
+                    date_delimiter: &mock.date_delimiter,
+                    date_month_style: &mock.date_month_style,
+                    date_language: &mock.date_language,
+                    date_capitalize: mock.date_capitalize,
+                },
+                &mut || Ok(()),
             )
         })
     });
     Ok(())
 }
 
-// fn remove_duplicates_benchmark(c: &mut Criterion) -> Result<(), Box<dyn std::error::Error>> {
-
-// }
+fn remove_duplicates_benchmark(c: &mut Criterion) -> Result<(), Box<dyn std::error::Error>> {
+    // every transaction is already in the workbook, the worst case for the old scan
+    let mock = excel::test_setup::create_mock_transactions(MOCK_TRANSACTIONS * 10)?;
+    c.bench_function("remove_duplicates", |b| {
+        b.iter(|| excel::lib::remove_duplicates(mock.info.clone(), mock.info.clone()))
+    });
+    Ok(())
+}
 
-criterion_group!{
+criterion_group! {
     name = benches_creating_mock_transactions;
     // This can be any expression that returns a `Criterion` object.
     config = Criterion::default().significance_level(0.1).sample_size(10);
     targets = create_mock_transactions_benchmark
 }
-criterion_group!{
+criterion_group! {
     name = benches_excel;
     // This can be any expression that returns a `Criterion` object.
     config = Criterion::default().significance_level(0.1).sample_size(10);
     targets = write_benchmark
 }
-criterion_group!(benches, create_mock_transactions_benchmark);
-criterion_main!(benches);
\ No newline at end of file
+criterion_group!(
+    benches,
+    create_mock_transactions_benchmark,
+    remove_duplicates_benchmark
+);
+criterion_main!(benches);
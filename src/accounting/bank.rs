@@ -1,123 +1,498 @@
 use chrono::NaiveDate;
 use std::error;
+use std::fmt;
 
-use crate::excel;
+use super::camt::Camt053;
+use super::money::Money;
+use super::mt940::Mt940;
+use super::ofx::Ofx;
+use super::profile::ImportProfile;
+use super::qif::Qif;
+use super::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
 use crate::file;
 
-#[derive(PartialEq, Copy, Clone)]
-pub enum Bank {
-    SBanken,
+// Something that can read the transactions out of a bank export
+pub trait BankImporter: Sync {
+    // the name shown in the bank combobox
+    fn name(&self) -> &str;
+
+    // the file extensions the transaction file dialog should accept
+    fn extensions(&self) -> &[&str];
+
+    // reads the transactions in the order they appear in the export
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>>;
+
+    // reads the rows it can and reports the ones it could not read instead of failing,
+    // importers that cannot skip a row read the export the same way as parse
+    fn parse_lenient(
+        &self,
+        path: &str,
+    ) -> Result<(Transactions, Vec<SkippedRow>), Box<dyn error::Error>> {
+        Ok((self.parse(path)?, vec![]))
+    }
+
+    // true if parse_lenient can leave out a row, so the GUI can tell when it cannot
+    fn skips_rows(&self) -> bool {
+        false
+    }
+
+    // the export with its opening and closing balance, none if the format does not have them
+    fn statement(&self, _path: &str) -> Result<Option<Statement>, Box<dyn error::Error>> {
+        Ok(None)
+    }
+}
+
+// A row of an export a lenient import left out, line is the line (1-indexed) in the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedRow {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for SkippedRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+// The rows of an export, each one either a transaction or the reason it could not be read
+pub type ReadRows = Vec<(usize, Result<Transaction, Box<dyn error::Error>>)>;
+
+// the transactions of the rows, the first row that could not be read is the error unless
+// the import is lenient
+pub fn collect_rows(
+    rows: ReadRows,
+    lenient: bool,
+) -> Result<(Transactions, Vec<SkippedRow>), Box<dyn error::Error>> {
+    let mut transactions = Transactions::new();
+    let mut skipped = vec![];
+    for (line, row) in rows {
+        match row {
+            Ok(transaction) => transactions.push(transaction),
+            Err(e) if lenient => skipped.push(SkippedRow {
+                line,
+                reason: skip_reason(e.as_ref()),
+            }),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((transactions, skipped))
+}
+
+// the skipped row already tells the line, so only the column and the value are kept
+fn skip_reason(e: &(dyn error::Error + 'static)) -> String {
+    let place = |location: &Location| match location {
+        Location::Line { column, .. } => column.clone(),
+        location => location.to_string(),
+    };
+    match e.downcast_ref::<Error>() {
+        Some(Error::BadDate {
+            location, value, ..
+        }) => format!("{}: '{}' is not a date", place(location), value),
+        Some(Error::BadAmount {
+            location, value, ..
+        }) => format!("{}: '{}' is not an amount", place(location), value),
+        _ => e.to_string(),
+    }
+}
+
+// every importer the program knows about, in the order they are shown in the GUI
+static IMPORTERS: &[&dyn BankImporter] = &[
+    &SBanken,
+    &Dnb,
+    &Nordea,
+    &Handelsbanken,
+    &Ofx,
+    &Camt053,
+    &Mt940,
+    &Qif,
+];
+
+// A handle to one of the registered importers
+#[derive(PartialEq, Copy, Clone, Debug, Default)]
+pub struct Bank {
+    index: usize,
 }
 
 impl Bank {
     pub fn get_banks() -> Vec<Bank> {
-        vec![Bank::SBanken]
+        (0..IMPORTERS.len()).map(|index| Bank { index }).collect()
     }
 
     pub fn to_string(bank: &Bank) -> String {
-        match bank {
-            Bank::SBanken => String::from("SBanken"),
+        String::from(bank.importer().name())
+    }
+
+    pub fn importer(&self) -> &'static dyn BankImporter {
+        IMPORTERS[self.index]
+    }
+}
+
+// A booked balance on a statement, positive when in credit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Balance {
+    pub date: NaiveDate,
+    pub amount: Money,
+}
+
+// A statement that also tells the balance before and after its transactions
+#[derive(Debug, Default, PartialEq)]
+pub struct Statement {
+    pub transactions: Transactions,
+    pub opening_balance: Option<Balance>,
+    pub closing_balance: Option<Balance>,
+}
+
+// The movement a statement claims between its balances, compared to what was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconciliation {
+    pub expected: Money,
+    pub found: Money,
+}
+
+impl Reconciliation {
+    pub fn is_ok(&self) -> bool {
+        self.expected == self.found
+    }
+}
+
+impl fmt::Display for Reconciliation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_ok() {
+            write!(
+                f,
+                "the transactions add up to {} as the balances say",
+                self.found
+            )
+        } else {
+            write!(
+                f,
+                "the balances say {} but the transactions add up to {}",
+                self.expected, self.found
+            )
         }
     }
 }
 
+impl Statement {
+    fn movement(&self) -> Result<Money, Box<dyn error::Error>> {
+        match (self.opening_balance, self.closing_balance) {
+            (Some(opening), Some(closing)) => Ok(closing.amount - opening.amount),
+            _ => Err("the statement does not have both an opening and a closing balance".into()),
+        }
+    }
+
+    // checks that the transactions add up to the difference between the balances
+    pub fn reconcile(&self) -> Result<Reconciliation, Box<dyn error::Error>> {
+        Ok(Reconciliation {
+            expected: self.movement()?,
+            found: self.transactions.sum(),
+        })
+    }
+
+    // checks the balances against the rows of the account in the workbook for the same period
+    pub fn reconcile_workbook(
+        &self,
+        transactions: &Transactions,
+        account: &str,
+    ) -> Result<Reconciliation, Box<dyn error::Error>> {
+        let expected = self.movement()?;
+        // the opening balance is the balance at the start of its date and the closing balance
+        // at the end of its date, camt OPBD and MT940 :60F: are usually dated on the first day
+        // of the period, so the rows booked on that day are part of it
+        let (from, to) = match (self.opening_balance, self.closing_balance) {
+            (Some(opening), Some(closing)) => (opening.date, closing.date),
+            _ => return Err("the statement does not have both balances".into()),
+        };
+        Ok(Reconciliation {
+            expected,
+            found: transactions.for_account(account).between(from, to).sum(),
+        })
+    }
+}
+
 #[derive(Debug)]
-pub struct SBanken {
-    pub accounting_date: Vec<NaiveDate>,
-    pub interest_date: Vec<NaiveDate>,
-    pub archive_reference: Vec<String>,
-    pub counter_account: Vec<String>,
-    pub types: Vec<String>,
-    pub text: Vec<String>,
-    pub out_of_account: Vec<f64>,
-    pub into_account: Vec<f64>,
+pub struct SBanken;
+
+impl BankImporter for SBanken {
+    fn name(&self) -> &str {
+        "SBanken"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xlsx"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        SBanken::get_transactions(path)
+    }
+
+    fn parse_lenient(
+        &self,
+        path: &str,
+    ) -> Result<(Transactions, Vec<SkippedRow>), Box<dyn error::Error>> {
+        ImportProfile::sbanken().parse_lenient(path)
+    }
+
+    fn skips_rows(&self) -> bool {
+        true
+    }
 }
 
 impl SBanken {
-    pub fn get_transactions(path: &str) -> Result<Self, Box<dyn error::Error>> {
-        let book =
-            file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-        let sheet = book
-            .get_sheet_by_name("Kontoutskrift")
-            .map_err(|e| format!("could not open worksheet 'Kontoutskrift': {:?}", e))?;
-
-        let mut accounting_date = vec![];
-        let mut interest_date = vec![];
-        let mut archive_reference = vec![];
-        let mut counter_account = vec![];
-        let mut types = vec![];
-        let mut text = vec![];
-        let mut out_of_account = vec![];
-        let mut into_account = vec![];
-
-        // sbanken transactions start on excel line 4
-        let mut row = 4;
-        loop {
-            // accounting date
-            let accounting_date_str =
-                &sheet.get_formatted_value(&(String::from("A") + &row.to_string()));
-            // if accounting_date_str is empty, end of transactions
-            if accounting_date_str == "" {
-                break;
-            }
-            let s = SBanken::string_to_date(accounting_date_str).map_err(|e| {
-                format!(
-                    "could not convert accounting date string to date for sbanken transaction: {:?}",
-                    e
-                )
-            })?;
-            accounting_date.push(s);
-
-            // interest date
-            let interest_date_str =
-                &sheet.get_formatted_value(&(String::from("B") + &row.to_string()));
-            let s = SBanken::string_to_date(interest_date_str).map_err(|e| {
-                format!(
-                    "could not convert interest date string to date for sbanken transaction: {:?}",
-                    e
-                )
-            })?;
-            interest_date.push(s);
-
-            archive_reference.push(sheet.get_value(&(String::from("C") + &row.to_string())));
-            counter_account.push(sheet.get_value(&(String::from("D") + &row.to_string())));
-            types.push(sheet.get_value(&(String::from("E") + &row.to_string())));
-            text.push(sheet.get_value(&(String::from("F") + &row.to_string())));
-
-            let out_of_account_str = sheet.get_value(&(String::from("G") + &row.to_string()));
-            match out_of_account_str.parse::<f64>() {
-                Ok(f) => out_of_account.push(f.abs()),
-                Err(_) => return Err("could not parse out of account string to f64".into()),
-            }
+    pub fn get_transactions(path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        ImportProfile::sbanken().get_transactions(path)
+    }
+
+    pub fn string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+        ImportProfile::sbanken().string_to_date(date)
+    }
+}
+
+#[derive(Debug)]
+pub struct Dnb;
+
+impl BankImporter for Dnb {
+    fn name(&self) -> &str {
+        "DNB"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xlsx"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Dnb::get_transactions(path)
+    }
+
+    fn parse_lenient(
+        &self,
+        path: &str,
+    ) -> Result<(Transactions, Vec<SkippedRow>), Box<dyn error::Error>> {
+        ImportProfile::dnb().parse_lenient(path)
+    }
+
+    fn skips_rows(&self) -> bool {
+        true
+    }
+}
+
+impl Dnb {
+    // DNB exports one signed amount column instead of separate columns for out of and into account
+    pub fn get_transactions(path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        ImportProfile::dnb().get_transactions(path)
+    }
 
-            let into_account_str = sheet.get_value(&(String::from("H") + &row.to_string()));
-            match into_account_str.parse::<f64>() {
-                Ok(f) => into_account.push(f),
-                Err(_) => return Err("could not parse into account string to f64".into()),
+    pub fn string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+        ImportProfile::dnb().string_to_date(date)
+    }
+}
+
+#[derive(Debug)]
+pub struct Nordea;
+
+impl BankImporter for Nordea {
+    fn name(&self) -> &str {
+        "Nordea"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv", "txt"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Nordea::get_transactions(path)
+    }
+
+    fn parse_lenient(
+        &self,
+        path: &str,
+    ) -> Result<(Transactions, Vec<SkippedRow>), Box<dyn error::Error>> {
+        collect_rows(Nordea::read_rows(path)?, true)
+    }
+
+    fn skips_rows(&self) -> bool {
+        true
+    }
+}
+
+impl Nordea {
+    // Bokføringsdato;Beløp;Avsender;Mottaker;Navn;Tittel;Valuta;Betalingstype
+    pub fn get_transactions(path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Ok(collect_rows(Nordea::read_rows(path)?, false)?.0)
+    }
+
+    fn read_rows(path: &str) -> Result<ReadRows, Box<dyn error::Error>> {
+        let rows = file::lib::read_csv(path, ';')?;
+        let ((_, header), rows) = rows.split_first().ok_or("the nordea file is empty")?;
+        let date_col = csv_column(header, "Bokføringsdato")?;
+        let amount_col = csv_column(header, "Beløp")?;
+        let sender_col = csv_column(header, "Avsender")?;
+        let receiver_col = csv_column(header, "Mottaker")?;
+        let name_col = csv_column(header, "Navn")?;
+        let title_col = csv_column(header, "Tittel")?;
+        // older exports do not have the payment type
+        let type_col = csv_column(header, "Betalingstype").ok();
+        let currency_col = csv_column(header, "Valuta").ok();
+
+        let mut read = ReadRows::new();
+        for (line, row) in rows {
+            let line = *line;
+            let field = |col: usize| row.get(col).cloned().unwrap_or_default();
+            // reserved transactions are not booked yet and have no date
+            if field(date_col) == "Reservert" {
+                continue;
             }
+            let transaction = || -> Result<Transaction, Box<dyn error::Error>> {
+                let date = Nordea::string_to_date(&field(date_col)).map_err(|_| {
+                    Error::bad_date(
+                        path,
+                        Location::line(line, &header[date_col]),
+                        &field(date_col),
+                    )
+                })?;
+                let amount = parse_amount(&field(amount_col)).map_err(|_| {
+                    Error::bad_amount(
+                        path,
+                        Location::line(line, &header[amount_col]),
+                        &field(amount_col),
+                    )
+                })?;
 
-            row += 1;
+                // the counter account is the receiver when paying and the sender when receiving
+                let counter_account = if amount.is_negative() {
+                    field(receiver_col)
+                } else {
+                    field(sender_col)
+                };
+                let title = field(title_col);
+                let text = if title.is_empty() {
+                    field(name_col)
+                } else {
+                    title
+                };
+                Ok(Transaction {
+                    counter_account,
+                    types: type_col.map(field).unwrap_or_default(),
+                    text,
+                    currency: currency_col.map(field).unwrap_or_default(),
+                    ..Transaction::new(date, amount)
+                })
+            };
+            read.push((line, transaction()));
         }
+        Ok(read)
+    }
 
-        let sbanken = SBanken {
-            accounting_date: accounting_date,
-            interest_date: interest_date,
-            archive_reference: archive_reference,
-            counter_account: counter_account,
-            types: types,
-            text: text,
-            out_of_account: out_of_account,
-            into_account: into_account,
-        };
-        Ok(sbanken)
+    pub fn string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+        iso_string_to_date(date)
+    }
+}
+
+#[derive(Debug)]
+pub struct Handelsbanken;
+
+impl BankImporter for Handelsbanken {
+    fn name(&self) -> &str {
+        "Handelsbanken"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv", "txt"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Handelsbanken::get_transactions(path)
+    }
+
+    fn parse_lenient(
+        &self,
+        path: &str,
+    ) -> Result<(Transactions, Vec<SkippedRow>), Box<dyn error::Error>> {
+        collect_rows(Handelsbanken::read_rows(path)?, true)
+    }
+
+    fn skips_rows(&self) -> bool {
+        true
+    }
+}
+
+impl Handelsbanken {
+    // Bokføringsdato;Rentedato;Tekst;Beløp;Saldo
+    pub fn get_transactions(path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Ok(collect_rows(Handelsbanken::read_rows(path)?, false)?.0)
+    }
+
+    fn read_rows(path: &str) -> Result<ReadRows, Box<dyn error::Error>> {
+        let rows = file::lib::read_csv(path, ';')?;
+        let ((_, header), rows) = rows
+            .split_first()
+            .ok_or("the handelsbanken file is empty")?;
+        let accounting_date_col = csv_column(header, "Bokføringsdato")?;
+        let interest_date_col = csv_column(header, "Rentedato")?;
+        let text_col = csv_column(header, "Tekst")?;
+        let amount_col = csv_column(header, "Beløp")?;
+
+        let mut read = ReadRows::new();
+        for (line, row) in rows {
+            let line = *line;
+            let field = |col: usize| row.get(col).cloned().unwrap_or_default();
+            let date = |col: usize| {
+                Handelsbanken::string_to_date(&field(col)).map_err(|_| {
+                    Error::bad_date(path, Location::line(line, &header[col]), &field(col))
+                })
+            };
+            let transaction = || -> Result<Transaction, Box<dyn error::Error>> {
+                let accounting_date = date(accounting_date_col)?;
+                let interest_date = date(interest_date_col)?;
+                let amount = parse_amount(&field(amount_col)).map_err(|_| {
+                    Error::bad_amount(
+                        path,
+                        Location::line(line, &header[amount_col]),
+                        &field(amount_col),
+                    )
+                })?;
+
+                Ok(Transaction {
+                    interest_date,
+                    text: field(text_col),
+                    ..Transaction::new(accounting_date, amount)
+                })
+            };
+            read.push((line, transaction()));
+        }
+        Ok(read)
     }
 
     pub fn string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
-        Ok(excel::lib::string_to_date(date, ".")
-            .map_err(|e| format!("date str is not valid: {:?}", e))?)
+        iso_string_to_date(date)
     }
 }
 
+// finds the index of a column in the header of a csv export
+fn csv_column(header: &[String], name: &str) -> Result<usize, Box<dyn error::Error>> {
+    Ok(header
+        .iter()
+        .position(|h| h.eq_ignore_ascii_case(name))
+        .ok_or(format!("could not find the column '{}'", name))?)
+}
+
+pub fn iso_string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+    Ok(NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("date str is not valid: {:?}", e))?)
+}
+
+// parses amounts like "-1 234,56" and "1234.56"
+pub fn parse_amount(amount: &str) -> Result<Money, Box<dyn error::Error>> {
+    let s: String = amount
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\u{a0}')
+        .map(|c| if c == ',' { '.' } else { c })
+        .collect();
+    Ok(Money::parse(&s).map_err(|e| format!("'{}' is not a valid amount: {:?}", amount, e))?)
+}
+
 #[cfg(test)]
 mod tests_bank {
     use super::*;
@@ -129,4 +504,190 @@ mod tests_bank {
         let date = SBanken::string_to_date("fail");
         assert!(date.is_err());
     }
+
+    #[test]
+    fn test_get_banks() {
+        let banks = Bank::get_banks();
+        assert_eq!(banks.len(), IMPORTERS.len());
+        assert_eq!(Bank::to_string(&banks[0]), "SBanken");
+        assert!(Bank::default().importer().extensions().contains(&"xlsx"));
+    }
+
+    #[test]
+    fn test_string_to_date_dnb() {
+        let date = Dnb::string_to_date("2022-06-03");
+        assert!(date.is_ok());
+        let date = Dnb::string_to_date("03.06.2022");
+        assert!(date.is_err());
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        assert_eq!(parse_amount("-1 234,56").unwrap(), Money::from_ore(-123456));
+        assert_eq!(parse_amount("1234.5").unwrap(), Money::from_ore(123450));
+        assert!(parse_amount("fail").is_err());
+    }
+
+    #[test]
+    fn test_get_transactions_dnb() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_dnb.xlsx";
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.new_sheet("Transaksjoner")?;
+        sheet.get_cell_mut("A2").set_value("2022-06-03");
+        sheet.get_cell_mut("B2").set_value("KIWI");
+        sheet.get_cell_mut("C2").set_value("2022-06-04");
+        sheet.get_cell_mut("D2").set_value("-12.5");
+        sheet.get_cell_mut("A3").set_value("2022-06-01");
+        sheet.get_cell_mut("B3").set_value("Lønn");
+        sheet.get_cell_mut("D3").set_value("100");
+        umya_spreadsheet::writer::xlsx::write(&book, path)
+            .map_err(|e| format!("could not write workbook: {:?}", e))?;
+
+        let transactions = Dnb.parse(path);
+        std::fs::remove_file(path)?;
+        let transactions = transactions?;
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["KIWI", "Lønn"]
+        );
+        assert_eq!(
+            transactions[1].interest_date,
+            transactions[1].accounting_date
+        );
+        assert_eq!(
+            transactions.column(|t| t.amount().to_f64()),
+            vec![-12.5, 100.0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_transactions_nordea() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_nordea.csv";
+        std::fs::write(
+            path,
+            "Bokføringsdato;Beløp;Avsender;Mottaker;Navn;Tittel;Valuta;Betalingstype\n\
+             Reservert;-50,00;1111;;;Pending;NOK;Varekjøp\n\
+             2022-06-03;-1 234,50;1111;2222;Kiwi;KIWI 123;NOK;Varekjøp\n\
+             2022-06-01;500,00;3333;1111;Ola;;EUR;Overføring\n",
+        )?;
+        let transactions = Nordea.parse(path);
+        std::fs::remove_file(path)?;
+        let transactions = transactions?;
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions.column(|t| t.counter_account.clone()),
+            vec!["2222", "3333"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["KIWI 123", "Ola"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.types.clone()),
+            vec!["Varekjøp", "Overføring"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.amount().to_f64()),
+            vec![-1234.5, 500.0]
+        );
+        assert_eq!(
+            transactions.column(|t| t.currency.clone()),
+            vec!["NOK", "EUR"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lenient() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_lenient.csv";
+        std::fs::write(
+            path,
+            "Bokføringsdato;Rentedato;Tekst;Beløp;Saldo\n\
+             2022-06-03;2022-06-03;KIWI;-12,50;100,00\n\
+             \n\
+             2022-06-04;2022-06-04;Vipps;12,5o;100,00\n\
+             2022-06-05;2022-06-05;Salary;1000,00;1100,00\n",
+        )?;
+        let strict = Handelsbanken.parse(path);
+        let lenient = Handelsbanken.parse_lenient(path);
+        std::fs::remove_file(path)?;
+        assert!(strict.is_err());
+        let (transactions, skipped) = lenient?;
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["KIWI", "Salary"]
+        );
+        assert_eq!(skipped.len(), 1);
+        // the empty line is counted
+        assert_eq!(skipped[0].line, 4);
+        assert_eq!(
+            skipped[0].to_string(),
+            "line 4: Beløp: '12,5o' is not an amount"
+        );
+        // the formats that are read whole say so
+        assert!(Handelsbanken.skips_rows());
+        assert!(!Ofx.skips_rows() && !Qif.skips_rows());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reconcile() -> Result<(), Box<dyn error::Error>> {
+        let mut statement = Statement::default();
+        let opening = NaiveDate::from_ymd(2022, 6, 1);
+        let date = NaiveDate::from_ymd(2022, 6, 2);
+        statement
+            .transactions
+            .push(Transaction::new(opening, Money::from_ore(-2550)));
+        statement
+            .transactions
+            .push(Transaction::new(date, Money::from_kroner(10)));
+        assert!(statement.reconcile().is_err());
+
+        statement.opening_balance = Some(Balance {
+            date: opening,
+            amount: Money::from_kroner(100),
+        });
+        statement.closing_balance = Some(Balance {
+            date: NaiveDate::from_ymd(2022, 6, 30),
+            amount: Money::from_ore(8450),
+        });
+        assert!(statement.reconcile()?.is_ok());
+
+        let mut workbook = Transactions::from(vec![
+            Transaction::new(NaiveDate::from_ymd(2022, 5, 31), Money::from_kroner(-50)),
+            Transaction::new(opening, Money::from_ore(-2550)),
+            Transaction::new(date, Money::from_kroner(10)),
+        ]);
+        workbook.set_account("a");
+        // the first row is before the period of the statement, the second is booked on the
+        // date of the opening balance and is part of it
+        let reconciliation = statement.reconcile_workbook(&workbook, "a")?;
+        assert_eq!(reconciliation.found, Money::from_ore(-1550));
+        assert!(reconciliation.is_ok());
+        assert!(!statement.reconcile_workbook(&workbook, "b")?.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_transactions_handelsbanken() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_handelsbanken.csv";
+        std::fs::write(
+            path,
+            "Bokføringsdato;Rentedato;Tekst;Beløp;Saldo\n\
+             2022-06-03;2022-06-02;REMA 1000;-99,90;900,10\n",
+        )?;
+        let transactions = Handelsbanken.parse(path);
+        std::fs::remove_file(path)?;
+        let transactions = transactions?;
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].text, "REMA 1000");
+        assert_eq!(
+            transactions[0].interest_date,
+            Handelsbanken::string_to_date("2022-06-02")?
+        );
+        assert_eq!(transactions[0].out_of_account, Money::from_ore(9990));
+        Ok(())
+    }
 }
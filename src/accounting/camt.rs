@@ -0,0 +1,467 @@
+use chrono::NaiveDate;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::error;
+use std::fs;
+
+use super::bank;
+use super::money::Money;
+use super::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
+
+// Reads ISO 20022 camt.053 bank-to-customer statements
+#[derive(Debug)]
+pub struct Camt053;
+
+impl bank::BankImporter for Camt053 {
+    fn name(&self) -> &str {
+        "camt.053"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xml"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Ok(Camt053::get_statement(path)?.transactions)
+    }
+
+    fn statement(&self, path: &str) -> Result<Option<bank::Statement>, Box<dyn error::Error>> {
+        Ok(Some(Camt053::get_statement(path)?))
+    }
+}
+
+impl Camt053 {
+    pub fn get_statement(path: &str) -> Result<bank::Statement, Box<dyn error::Error>> {
+        let content = fs::read_to_string(path).map_err(|e| Error::Io {
+            file: String::from(path),
+            reason: format!("{:?}", e),
+        })?;
+        Camt053::parse_str(path, &content)
+    }
+
+    pub fn parse_str(path: &str, content: &str) -> Result<bank::Statement, Box<dyn error::Error>> {
+        let document = Element::parse(path, content)?;
+        let statements = document
+            .find("BkToCstmrStmt")
+            .ok_or_else(|| Error::NotStatement {
+                file: String::from(path),
+                format: String::from("camt.053"),
+            })?
+            .children("Stmt");
+
+        let mut statement = bank::Statement::default();
+        for stmt in statements {
+            for bal in stmt.children("Bal") {
+                let code = bal.text("Tp/CdOrPrtry/Cd");
+                // the opening balance of the first statement and the closing balance of the last
+                if (code == "OPBD" || code == "PRCD") && statement.opening_balance.is_none() {
+                    statement.opening_balance = Some(Camt053::get_balance(path, bal)?);
+                } else if code == "CLBD" {
+                    statement.closing_balance = Some(Camt053::get_balance(path, bal)?);
+                }
+            }
+            for ntry in stmt.children("Ntry") {
+                Camt053::push_entry(path, &mut statement.transactions, ntry)?;
+            }
+        }
+        Ok(statement)
+    }
+
+    fn push_entry(
+        path: &str,
+        transactions: &mut Transactions,
+        ntry: &Element,
+    ) -> Result<(), Error> {
+        let accounting_date = Camt053::get_date(path, ntry, "BookgDt")?;
+        let interest_date = match ntry.find("ValDt") {
+            Some(_) => Camt053::get_date(path, ntry, "ValDt")?,
+            None => accounting_date,
+        };
+        let amount = Camt053::get_amount(path, ntry)?;
+
+        // batch entries can have several transactions, the first describes the entry
+        let empty = Element::default();
+        let tx = ntry.find("NtryDtls/TxDtls").unwrap_or(&empty);
+
+        let mut reference = tx.text("Refs/EndToEndId");
+        if reference.is_empty() || reference == "NOTPROVIDED" {
+            reference = ntry.text("AcctSvcrRef");
+        }
+
+        // the counterparty is the creditor when paying and the debtor when receiving
+        let party = if amount.is_negative() { "Cdtr" } else { "Dbtr" };
+        let mut counter_account = tx.text(&format!("RltdPties/{}Acct/Id/IBAN", party));
+        if counter_account.is_empty() {
+            counter_account = tx.text(&format!("RltdPties/{}Acct/Id/Othr/Id", party));
+        }
+        let mut name = tx.text(&format!("RltdPties/{}/Nm", party));
+        if name.is_empty() {
+            name = tx.text(&format!("RltdPties/{}/Pty/Nm", party));
+        }
+
+        let remittance: Vec<String> = tx
+            .find("RmtInf")
+            .map(|rmt| rmt.children("Ustrd").map(|u| u.text.clone()).collect())
+            .unwrap_or_default();
+        let text = if remittance.is_empty() {
+            name
+        } else {
+            remittance.join(" ")
+        };
+
+        let mut transaction = Transaction {
+            interest_date,
+            archive_reference: reference,
+            counter_account,
+            types: ntry.text("AddtlNtryInf"),
+            text,
+            currency: ntry.attribute("Amt", "Ccy"),
+            ..Transaction::new(accounting_date, amount)
+        };
+        // the amount the payer instructed, e.g. a card payment in EUR booked in NOK
+        let original_currency = tx.attribute("AmtDtls/InstdAmt/Amt", "Ccy");
+        if !original_currency.is_empty() && original_currency != transaction.currency {
+            let instructed = "AmtDtls/InstdAmt/Amt";
+            let original_amount = bank::parse_amount(&tx.text(instructed)).map_err(|_| {
+                Error::bad_amount(path, tx.location(instructed), &tx.text(instructed))
+            })?;
+            transaction.original_amount = if amount.is_negative() {
+                -original_amount
+            } else {
+                original_amount
+            };
+            transaction.original_currency = original_currency;
+        }
+        transactions.push(transaction);
+        Ok(())
+    }
+
+    fn get_balance(path: &str, bal: &Element) -> Result<bank::Balance, Error> {
+        Ok(bank::Balance {
+            date: Camt053::get_date(path, bal, "Dt")?,
+            amount: Camt053::get_amount(path, bal)?,
+        })
+    }
+
+    // the amount is always positive, CdtDbtInd tells the direction
+    fn get_amount(path: &str, element: &Element) -> Result<Money, Error> {
+        let amount = bank::parse_amount(&element.text("Amt"))
+            .map_err(|_| Error::bad_amount(path, element.location("Amt"), &element.text("Amt")))?;
+        match element.text("CdtDbtInd").as_str() {
+            "DBIT" => Ok(-amount),
+            "CRDT" => Ok(amount),
+            other => Err(Error::bad_value(
+                path,
+                element.location("CdtDbtInd"),
+                other,
+                "a credit/debit indicator",
+            )),
+        }
+    }
+
+    // dates are either <Dt> or <DtTm>
+    fn get_date(path: &str, element: &Element, name: &str) -> Result<NaiveDate, Error> {
+        let mut child = format!("{}/Dt", name);
+        let mut date = element.text(&child);
+        if date.is_empty() {
+            child = format!("{}/DtTm", name);
+            date = element.text(&child).chars().take(10).collect();
+        }
+        bank::iso_string_to_date(&date)
+            .map_err(|_| Error::bad_date(path, element.location(&child), &element.text(&child)))
+    }
+}
+
+// A minimal XML element tree, namespaces are stripped from the names
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    // the line the start tag is on
+    line: usize,
+    text: String,
+    // e.g. ("Ccy", "NOK") for <Amt Ccy="NOK">
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn parse(path: &str, content: &str) -> Result<Element, Error> {
+        let mut reader = Reader::from_str(content);
+        reader.trim_text(true);
+        // the reader knows the byte it is at, the errors tell the line
+        let line = |reader: &Reader<&[u8]>| {
+            let position = reader.buffer_position().min(content.len());
+            content.as_bytes()[..position]
+                .iter()
+                .filter(|b| **b == b'\n')
+                .count()
+                + 1
+        };
+        let bad_xml = |reader: &Reader<&[u8]>, reason: &str| {
+            Error::bad_format(path, Location::line(line(reader), ""), reason)
+        };
+
+        let mut stack = vec![Element::default()];
+        let mut buf = vec![];
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(e)) => {
+                    let element = Element::start(&e, &reader, line(&reader))
+                        .map_err(|reason| bad_xml(&reader, &reason))?;
+                    stack.push(element);
+                }
+                Ok(Event::Empty(e)) => {
+                    let element = Element::start(&e, &reader, line(&reader))
+                        .map_err(|reason| bad_xml(&reader, &reason))?;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                }
+                Ok(Event::Text(e)) => {
+                    let text = e.unescape_and_decode(&reader).map_err(|e| {
+                        bad_xml(&reader, &format!("could not read the text: {:?}", e))
+                    })?;
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                }
+                Ok(Event::End(_)) => {
+                    let not_opened = || bad_xml(&reader, "an element is closed that is not open");
+                    let element = stack.pop().ok_or_else(not_opened)?;
+                    stack
+                        .last_mut()
+                        .ok_or_else(not_opened)?
+                        .children
+                        .push(element);
+                }
+                Ok(Event::Eof) => break,
+                Ok(_) => (),
+                Err(e) => {
+                    return Err(bad_xml(
+                        &reader,
+                        &format!("could not read the xml: {:?}", e),
+                    ))
+                }
+            }
+            buf.clear();
+        }
+        if stack.len() != 1 {
+            return Err(bad_xml(&reader, "an element is not closed"));
+        }
+        Ok(stack.remove(0))
+    }
+
+    fn start(e: &BytesStart, reader: &Reader<&[u8]>, line: usize) -> Result<Element, String> {
+        let mut attributes = vec![];
+        for attribute in e.attributes() {
+            let attribute =
+                attribute.map_err(|e| format!("could not read an attribute: {:?}", e))?;
+            let value = attribute
+                .unescape_and_decode_value(reader)
+                .map_err(|e| format!("could not read an attribute: {:?}", e))?;
+            attributes.push((String::from_utf8_lossy(attribute.key).to_string(), value));
+        }
+        Ok(Element {
+            name: String::from_utf8_lossy(e.local_name()).to_string(),
+            line,
+            attributes,
+            ..Element::default()
+        })
+    }
+
+    fn children<'a, 'b>(&'a self, name: &'b str) -> impl Iterator<Item = &'a Element> + 'b
+    where
+        'a: 'b,
+    {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    // follows a path like "Tp/CdOrPrtry/Cd", if the first name is not a child it searches deeper
+    fn find<'a>(&'a self, path: &str) -> Option<&'a Element> {
+        let mut names = path.split('/');
+        let first = names.next()?;
+        let mut element = self.search(first)?;
+        for name in names {
+            element = element.children(name).next()?;
+        }
+        Some(element)
+    }
+
+    fn search<'a>(&'a self, name: &str) -> Option<&'a Element> {
+        match self.children(name).next() {
+            Some(element) => Some(element),
+            None => self.children.iter().find_map(|c| c.search(name)),
+        }
+    }
+
+    // paths are relative, so only direct children are considered for the first name
+    fn child<'a>(&'a self, path: &str) -> Option<&'a Element> {
+        let mut element = self;
+        for name in path.split('/') {
+            element = element.children(name).next()?;
+        }
+        Some(element)
+    }
+
+    fn text(&self, path: &str) -> String {
+        self.child(path)
+            .map(|element| element.text.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    // where the element at the path is, or where this element is if it is missing,
+    // e.g. "Ntry/BookgDt/Dt"
+    fn location(&self, path: &str) -> Location {
+        let line = self.child(path).map_or(self.line, |element| element.line);
+        Location::element(line, &format!("{}/{}", self.name, path))
+    }
+
+    // the attribute of the element at the path, empty if either is missing
+    fn attribute(&self, path: &str, name: &str) -> String {
+        self.child(path)
+            .and_then(|element| element.attributes.iter().find(|(key, _)| key == name))
+            .map(|(_, value)| value.trim().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests_camt {
+    use super::*;
+
+    const STATEMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+          <BkToCstmrStmt>
+            <GrpHdr><MsgId>1</MsgId></GrpHdr>
+            <Stmt>
+              <Acct><Id><IBAN>NO9386011117947</IBAN></Id></Acct>
+              <Bal>
+                <Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp>
+                <Amt Ccy="NOK">1000.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+                <Dt><Dt>2022-05-31</Dt></Dt>
+              </Bal>
+              <Bal>
+                <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+                <Amt Ccy="NOK">1387.50</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+                <Dt><Dt>2022-06-30</Dt></Dt>
+              </Bal>
+              <Ntry>
+                <Amt Ccy="NOK">112.50</Amt><CdtDbtInd>DBIT</CdtDbtInd>
+                <BookgDt><Dt>2022-06-03</Dt></BookgDt>
+                <ValDt><Dt>2022-06-02</Dt></ValDt>
+                <AcctSvcrRef>REF1</AcctSvcrRef>
+                <NtryDtls><TxDtls>
+                  <Refs><EndToEndId>E2E1</EndToEndId></Refs>
+                  <AmtDtls><InstdAmt><Amt Ccy="EUR">11.25</Amt></InstdAmt></AmtDtls>
+                  <RltdPties>
+                    <Cdtr><Nm>Kiwi AS</Nm></Cdtr>
+                    <CdtrAcct><Id><Othr><Id>12345678903</Id></Othr></Id></CdtrAcct>
+                  </RltdPties>
+                  <RmtInf><Ustrd>Invoice 42</Ustrd></RmtInf>
+                </TxDtls></NtryDtls>
+                <AddtlNtryInf>Varekjøp</AddtlNtryInf>
+              </Ntry>
+              <Ntry>
+                <Amt Ccy="NOK">500.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>
+                <BookgDt><DtTm>2022-06-10T10:00:00</DtTm></BookgDt>
+                <AcctSvcrRef>REF2</AcctSvcrRef>
+                <NtryDtls><TxDtls>
+                  <Refs><EndToEndId>NOTPROVIDED</EndToEndId></Refs>
+                  <RltdPties><Dbtr><Nm>Customer &amp; Co</Nm></Dbtr></RltdPties>
+                </TxDtls></NtryDtls>
+              </Ntry>
+            </Stmt>
+          </BkToCstmrStmt>
+        </Document>"#;
+
+    #[test]
+    fn test_parse_str() -> Result<(), Box<dyn error::Error>> {
+        let statement = Camt053::parse_str("test.xml", STATEMENT)?;
+        let t = &statement.transactions;
+        assert_eq!(t.len(), 2);
+        assert_eq!(
+            t.column(|t| t.archive_reference.clone()),
+            vec!["E2E1", "REF2"]
+        );
+        assert_eq!(
+            t.column(|t| t.counter_account.clone()),
+            vec!["12345678903", ""]
+        );
+        assert_eq!(
+            t.column(|t| t.text.clone()),
+            vec!["Invoice 42", "Customer & Co"]
+        );
+        assert_eq!(t.column(|t| t.types.clone()), vec!["Varekjøp", ""]);
+        assert_eq!(t[0].interest_date, bank::iso_string_to_date("2022-06-02")?);
+        assert_eq!(
+            t[1].accounting_date,
+            bank::iso_string_to_date("2022-06-10")?
+        );
+        assert_eq!(t.column(|t| t.amount().to_f64()), vec![-112.5, 500.0]);
+        assert_eq!(t.column(|t| t.currency.clone()), vec!["NOK", "NOK"]);
+        assert_eq!(t[0].original_currency, "EUR");
+        assert_eq!(t[0].original_amount, Money::from_ore(-1125));
+        assert_eq!(t[1].original_currency, "");
+
+        assert_eq!(
+            statement.opening_balance.unwrap().amount,
+            Money::from_kroner(1000)
+        );
+        assert_eq!(
+            statement.closing_balance.unwrap().amount,
+            Money::from_ore(138750)
+        );
+        assert!(statement.reconcile()?.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_str_not_camt() {
+        let error = |content: &str| {
+            *Camt053::parse_str("test.xml", content)
+                .unwrap_err()
+                .downcast::<Error>()
+                .unwrap()
+        };
+        assert_eq!(
+            error("<Document></Document>"),
+            Error::NotStatement {
+                file: String::from("test.xml"),
+                format: String::from("camt.053"),
+            }
+        );
+        assert_eq!(
+            error("<Document>\n<BkToCstmrStmt>"),
+            Error::bad_format(
+                "test.xml",
+                Location::line(2, ""),
+                "an element is not closed"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_str_bad_value() -> Result<(), Box<dyn error::Error>> {
+        let content = STATEMENT.replace("<Dt>2022-06-03</Dt>", "<Dt>2022-06-31</Dt>");
+        let error = Camt053::parse_str("test.xml", &content).unwrap_err();
+        assert_eq!(
+            *error.downcast::<Error>()?,
+            Error::bad_date(
+                "test.xml",
+                Location::element(19, "Ntry/BookgDt/Dt"),
+                "2022-06-31"
+            )
+        );
+        let content = STATEMENT.replace(
+            "<CdtDbtInd>CRDT</CdtDbtInd>\n                <BookgDt>",
+            "<CdtDbtInd>CR</CdtDbtInd>\n                <BookgDt>",
+        );
+        let error = Camt053::parse_str("test.xml", &content).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "test.xml: line 34, element Ntry/CdtDbtInd: 'CR' is not a credit/debit indicator"
+        );
+        Ok(())
+    }
+}
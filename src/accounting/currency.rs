@@ -0,0 +1,219 @@
+use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+
+use super::bank;
+use super::money::Money;
+use super::transaction::Transactions;
+use crate::error::{Error, Location};
+use crate::file;
+
+// the currency of accounts and rows that do not say anything else
+pub const DEFAULT_CURRENCY: &str = "NOK";
+
+// Historical exchange rates into the reporting currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeRates {
+    pub reporting_currency: String,
+    // currency -> date -> the value of one unit in the reporting currency
+    rates: HashMap<String, BTreeMap<NaiveDate, f64>>,
+}
+
+impl Default for ExchangeRates {
+    fn default() -> Self {
+        ExchangeRates::new(DEFAULT_CURRENCY)
+    }
+}
+
+impl ExchangeRates {
+    pub fn new(reporting_currency: &str) -> Self {
+        ExchangeRates {
+            reporting_currency: normalize(reporting_currency),
+            rates: HashMap::new(),
+        }
+    }
+
+    // reads a csv with the header date;currency;rate, e.g. 2022-06-03;EUR;10.05
+    pub fn from_csv(path: &str, reporting_currency: &str) -> Result<Self, Box<dyn error::Error>> {
+        let content = std::fs::read_to_string(path).map_err(|e| Error::Io {
+            file: String::from(path),
+            reason: format!("{:?}", e),
+        })?;
+        ExchangeRates::from_str_csv(path, &content, reporting_currency)
+    }
+
+    pub fn from_str_csv(
+        path: &str,
+        content: &str,
+        reporting_currency: &str,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        // the rates use '.' as decimal separator when the file is comma separated
+        let first_line = content.lines().next().unwrap_or_default();
+        let delimiter = if first_line.contains(';') { ';' } else { ',' };
+        let rows = file::lib::parse_csv(content, delimiter);
+        let header = rows
+            .first()
+            .map_or(&[][..], |(_, header)| header.as_slice());
+        let column = |i: usize| header.get(i).map_or("", |name| name.as_str());
+
+        let mut rates = ExchangeRates::new(reporting_currency);
+        for (line, row) in rows.iter().skip(1) {
+            let line = *line;
+            if row.len() < 3 {
+                return Err(Error::bad_format(
+                    path,
+                    Location::line(line, ""),
+                    "a rate needs three columns, the date, the currency and the rate",
+                )
+                .into());
+            }
+            let date = bank::iso_string_to_date(&row[0])
+                .map_err(|_| Error::bad_date(path, Location::line(line, column(0)), &row[0]))?;
+            let rate = row[2].replace(',', ".").parse::<f64>().map_err(|_| {
+                Error::bad_value(
+                    path,
+                    Location::line(line, column(2)),
+                    &row[2],
+                    "an exchange rate",
+                )
+            })?;
+            rates.insert(&row[1], date, rate);
+        }
+        Ok(rates)
+    }
+
+    pub fn insert(&mut self, currency: &str, date: NaiveDate, rate: f64) {
+        self.rates
+            .entry(normalize(currency))
+            .or_default()
+            .insert(date, rate);
+    }
+
+    // the latest rate on or before the date, since there are no rates for weekends and holidays
+    pub fn rate(&self, currency: &str, date: NaiveDate) -> Result<f64, Box<dyn error::Error>> {
+        let currency = normalize(currency);
+        if currency == self.reporting_currency {
+            return Ok(1.0);
+        }
+        self.rates
+            .get(&currency)
+            .and_then(|rates| rates.range(..=date).next_back())
+            .map(|(_, rate)| *rate)
+            .ok_or_else(|| {
+                format!(
+                    "no exchange rate from {} to {} on or before {}",
+                    currency, self.reporting_currency, date
+                )
+                .into()
+            })
+    }
+
+    // the amount in the reporting currency, rounded to whole øre
+    pub fn convert(
+        &self,
+        amount: Money,
+        currency: &str,
+        date: NaiveDate,
+    ) -> Result<Money, Box<dyn error::Error>> {
+        let rate = self.rate(currency, date)?;
+        if rate == 1.0 {
+            return Ok(amount);
+        }
+        Ok(Money::from_f64(amount.to_f64() * rate))
+    }
+
+    // the sum of the transactions in the reporting currency
+    pub fn sum(&self, transactions: &Transactions) -> Result<Money, Box<dyn error::Error>> {
+        let mut sum = Money::ZERO;
+        for transaction in transactions {
+            sum += self.convert(
+                transaction.amount(),
+                &transaction.currency,
+                transaction.accounting_date,
+            )?;
+        }
+        Ok(sum)
+    }
+}
+
+// currency codes are compared in upper case, and no currency means the default currency
+pub fn normalize(currency: &str) -> String {
+    let currency = currency.trim().to_uppercase();
+    if currency.is_empty() {
+        String::from(DEFAULT_CURRENCY)
+    } else {
+        currency
+    }
+}
+
+#[cfg(test)]
+mod tests_currency {
+    use super::*;
+    use crate::accounting::transaction::Transaction;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2022, 6, day)
+    }
+
+    #[test]
+    fn test_from_str_csv() -> Result<(), Box<dyn error::Error>> {
+        let rates = ExchangeRates::from_str_csv(
+            "rates.csv",
+            "date;currency;rate\n2022-06-01;EUR;10,05\n2022-06-03;eur;10,20\n2022-06-01;SEK;0,98\n",
+            "nok",
+        )?;
+        assert_eq!(rates.reporting_currency, "NOK");
+        assert_eq!(rates.rate("EUR", date(1))?, 10.05);
+        // days without a rate use the latest rate before them
+        assert_eq!(rates.rate("EUR", date(2))?, 10.05);
+        assert_eq!(rates.rate("eur", date(5))?, 10.2);
+        assert_eq!(rates.rate("NOK", date(1))?, 1.0);
+        assert!(rates.rate("USD", date(1)).is_err());
+        assert!(rates.rate("SEK", NaiveDate::from_ymd(2022, 5, 31)).is_err());
+
+        let comma = ExchangeRates::from_str_csv(
+            "rates.csv",
+            "date,currency,rate\n2022-06-01,USD,9.5\n",
+            "NOK",
+        )?;
+        assert_eq!(comma.rate("USD", date(1))?, 9.5);
+        assert!(ExchangeRates::from_str_csv(
+            "rates.csv",
+            "date;currency;rate\n2022-06-01;EUR\n",
+            "NOK"
+        )
+        .is_err());
+        let error = ExchangeRates::from_str_csv(
+            "rates.csv",
+            "date;currency;rate\n\n2022-06-01;EUR;10,o5\n",
+            "NOK",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rates.csv: line 3, rate: '10,o5' is not an exchange rate"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert() -> Result<(), Box<dyn error::Error>> {
+        let mut rates = ExchangeRates::default();
+        rates.insert("EUR", date(1), 10.5);
+        assert_eq!(
+            rates.convert(Money::from_ore(-1250), "EUR", date(1))?,
+            Money::from_ore(-13125)
+        );
+        assert_eq!(
+            rates.convert(Money::from_ore(-1250), "", date(1))?,
+            Money::from_ore(-1250)
+        );
+
+        let mut eur = Transaction::new(date(1), Money::from_kroner(10));
+        eur.currency = String::from("EUR");
+        let nok = Transaction::new(date(1), Money::from_kroner(-50));
+        let transactions = Transactions::from(vec![eur, nok]);
+        assert_eq!(rates.sum(&transactions)?, Money::from_kroner(55));
+        Ok(())
+    }
+}
@@ -2,6 +2,8 @@
 pub struct Account {
     pub name: String,
     pub number: u64,
+    // the currency the bank keeps the account in
+    pub currency: String,
 }
 
 impl Account {
@@ -9,6 +11,7 @@ impl Account {
         Account {
             name: account.name.clone(),
             number: account.number.clone(),
+            currency: account.currency.clone(),
         }
     }
 
@@ -34,6 +37,7 @@ mod tests_accounting_lib {
         let a = Account {
             name: String::from("a"),
             number: 0,
+            currency: String::from("NOK"),
         };
         assert_eq!(a.to_string(), String::from("a: 0"));
     }
@@ -1,2 +1,10 @@
 pub mod bank;
-pub mod lib;
\ No newline at end of file
+pub mod camt;
+pub mod currency;
+pub mod lib;
+pub mod money;
+pub mod mt940;
+pub mod ofx;
+pub mod profile;
+pub mod qif;
+pub mod transaction;
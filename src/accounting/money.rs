@@ -0,0 +1,184 @@
+use std::error;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+// An amount of money in whole øre, so sums and comparisons are exact
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money {
+    ore: i64,
+}
+
+impl Money {
+    pub const ZERO: Money = Money { ore: 0 };
+
+    pub fn from_ore(ore: i64) -> Self {
+        Money { ore }
+    }
+
+    pub fn from_kroner(kroner: i64) -> Self {
+        Money { ore: kroner * 100 }
+    }
+
+    // rounds to the closest øre
+    pub fn from_f64(amount: f64) -> Self {
+        Money {
+            ore: (amount * 100.0).round() as i64,
+        }
+    }
+
+    pub fn ore(&self) -> i64 {
+        self.ore
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.ore as f64 / 100.0
+    }
+
+    pub fn abs(&self) -> Self {
+        Money {
+            ore: self.ore.abs(),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.ore < 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.ore == 0
+    }
+
+    // parses "-1234.56", "1234" and ".5" without going through f64,
+    // more than two decimals are rounded to the closest øre
+    pub fn parse(amount: &str) -> Result<Money, Box<dyn error::Error>> {
+        let invalid = || format!("'{}' is not a valid amount", amount);
+        let s = amount.trim();
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (kroner, decimals) = match s.split_once('.') {
+            Some((kroner, decimals)) => (kroner, decimals),
+            None => (s, ""),
+        };
+        if (kroner.is_empty() && decimals.is_empty())
+            || !kroner.chars().all(|c| c.is_ascii_digit())
+            || !decimals.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid().into());
+        }
+
+        let mut ore: i64 = if kroner.is_empty() {
+            0
+        } else {
+            kroner.parse::<i64>().map_err(|_| invalid())?
+        };
+        let mut digits = decimals.chars().map(|c| c.to_digit(10).unwrap() as i64);
+        for _ in 0..2 {
+            ore = ore
+                .checked_mul(10)
+                .and_then(|o| o.checked_add(digits.next().unwrap_or(0)))
+                .ok_or_else(invalid)?;
+        }
+        // the third decimal decides the rounding
+        if digits.next().unwrap_or(0) >= 5 {
+            ore += 1;
+        }
+        Ok(Money {
+            ore: if negative { -ore } else { ore },
+        })
+    }
+}
+
+// always with two decimals, e.g. "-12.50"
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.ore < 0 { "-" } else { "" };
+        let ore = self.ore.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, ore / 100, ore % 100)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money {
+            ore: self.ore + other.ore,
+        }
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money {
+            ore: self.ore - other.ore,
+        }
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money { ore: -self.ore }
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.ore += other.ore;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.ore -= other.ore;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |a, b| a + b)
+    }
+}
+
+#[cfg(test)]
+mod tests_money {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Money::parse("1234.56").unwrap().ore(), 123456);
+        assert_eq!(Money::parse("-12.5").unwrap().ore(), -1250);
+        assert_eq!(Money::parse("100").unwrap().ore(), 10000);
+        assert_eq!(Money::parse(".5").unwrap().ore(), 50);
+        // float noise from older workbooks
+        assert_eq!(Money::parse("0.30000000000000004").unwrap().ore(), 30);
+        assert_eq!(Money::parse("0.125").unwrap().ore(), 13);
+        assert!(Money::parse("").is_err());
+        assert!(Money::parse("1,5").is_err());
+        assert!(Money::parse("-").is_err());
+        assert!(Money::parse("1e5").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Money::from_ore(-1250).to_string(), "-12.50");
+        assert_eq!(Money::from_ore(5).to_string(), "0.05");
+        assert_eq!(Money::from_ore(-5).to_string(), "-0.05");
+        assert_eq!(Money::from_kroner(3).to_string(), "3.00");
+    }
+
+    #[test]
+    fn test_sum() {
+        let amounts = vec![Money::from_f64(0.1), Money::from_f64(0.2)];
+        let sum: Money = amounts.into_iter().sum();
+        assert_eq!(sum, Money::from_f64(0.3));
+        assert_eq!(sum.to_string(), "0.30");
+        assert_eq!(-sum + Money::from_ore(30), Money::ZERO);
+    }
+}
@@ -0,0 +1,388 @@
+use chrono::{Datelike, NaiveDate};
+use std::collections::HashMap;
+use std::error;
+use std::fs;
+
+use super::bank;
+use super::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
+
+// Reads SWIFT MT940 customer statements
+#[derive(Debug)]
+pub struct Mt940;
+
+impl bank::BankImporter for Mt940 {
+    fn name(&self) -> &str {
+        "MT940"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sta", "mt940", "940", "txt"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Ok(Mt940::get_statement(path)?.transactions)
+    }
+
+    fn statement(&self, path: &str) -> Result<Option<bank::Statement>, Box<dyn error::Error>> {
+        Ok(Some(Mt940::get_statement(path)?))
+    }
+}
+
+impl Mt940 {
+    pub fn get_statement(path: &str) -> Result<bank::Statement, Box<dyn error::Error>> {
+        let bytes = fs::read(path).map_err(|e| Error::Io {
+            file: String::from(path),
+            reason: format!("{:?}", e),
+        })?;
+        let content = match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => e.into_bytes().iter().map(|b| *b as char).collect(),
+        };
+        Mt940::parse_str(path, &content)
+    }
+
+    pub fn parse_str(path: &str, content: &str) -> Result<bank::Statement, Box<dyn error::Error>> {
+        let mut statement = bank::Statement::default();
+        let mut has_statement = false;
+        // the currency of the opening balance is the currency of the statement
+        let mut currency = String::new();
+
+        let fields = get_fields(content);
+        for (i, (line, tag, value)) in fields.iter().enumerate() {
+            let location = Location::line(*line, "");
+            match tag.as_str() {
+                "20" => has_statement = true,
+                "60F" | "60M" => {
+                    let balance = Mt940::get_balance(path, &location, value)?;
+                    currency = balance_currency(value);
+                    // the opening balance of the first statement in the file
+                    if statement.opening_balance.is_none() {
+                        statement.opening_balance = Some(balance);
+                    }
+                }
+                // the closing balance of the last statement in the file
+                "62F" | "62M" => {
+                    statement.closing_balance = Some(Mt940::get_balance(path, &location, value)?)
+                }
+                "61" => {
+                    // the information to the account owner belongs to the line before it
+                    let info = match fields.get(i + 1) {
+                        Some((_, tag, info)) if tag == "86" => info.as_str(),
+                        _ => "",
+                    };
+                    Mt940::push_line(
+                        path,
+                        &location,
+                        &mut statement.transactions,
+                        value,
+                        info,
+                        &currency,
+                    )?;
+                }
+                _ => (),
+            }
+        }
+        // every statement starts with the tag :20:
+        if !has_statement {
+            return Err(Error::NotStatement {
+                file: String::from(path),
+                format: String::from("MT940"),
+            }
+            .into());
+        }
+        Ok(statement)
+    }
+
+    // :61:YYMMDD[MMDD]C|D|RC|RD[funds code]amount type reference[//bank reference][\n details]
+    fn push_line(
+        path: &str,
+        location: &Location,
+        transactions: &mut Transactions,
+        line: &str,
+        info: &str,
+        currency: &str,
+    ) -> Result<(), Error> {
+        let first_line = line.lines().next().unwrap_or_default();
+        let chars: Vec<char> = first_line.chars().collect();
+        let take = |from: usize, to: usize| -> String {
+            chars[from.min(chars.len())..to.min(chars.len())]
+                .iter()
+                .collect()
+        };
+
+        let bad_date = |date: &str| Error::bad_date(path, location.clone(), date);
+
+        let value_date = yymmdd_to_date(&take(0, 6)).map_err(|_| bad_date(&take(0, 6)))?;
+        let mut pos = 6;
+        // the entry date is optional, and has no year
+        let accounting_date =
+            if chars.len() >= 10 && chars[6..10].iter().all(|c| c.is_ascii_digit()) {
+                pos = 10;
+                entry_date(value_date, &take(6, 10)).map_err(|_| bad_date(&take(6, 10)))?
+            } else {
+                value_date
+            };
+
+        let mark = if take(pos, pos + 2) == "RC" || take(pos, pos + 2) == "RD" {
+            take(pos, pos + 2)
+        } else {
+            take(pos, pos + 1)
+        };
+        pos += mark.len();
+        // the third character of the currency code, e.g. 'K' for NOK
+        if matches!(chars.get(pos), Some(c) if c.is_ascii_alphabetic()) {
+            pos += 1;
+        }
+
+        let amount_len = chars[pos.min(chars.len())..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit() || **c == ',')
+            .count();
+        let amount_str = take(pos, pos + amount_len);
+        let amount = bank::parse_amount(&amount_str)
+            .map_err(|_| Error::bad_amount(path, location.clone(), &amount_str))?;
+        pos += amount_len;
+        // debit and reversed credit take money out of the account
+        let amount = match mark.as_str() {
+            "D" | "RC" => -amount,
+            "C" | "RD" => amount,
+            _ => {
+                return Err(Error::bad_value(
+                    path,
+                    location.clone(),
+                    &mark,
+                    "a debit/credit mark",
+                ))
+            }
+        };
+
+        let types = take(pos, pos + 4);
+        pos += types.len();
+        let rest = take(pos, chars.len());
+        let (reference, bank_reference) = match rest.find("//") {
+            Some(i) => (rest[..i].to_string(), rest[i + 2..].to_string()),
+            None => (rest, String::new()),
+        };
+        let reference = if reference.is_empty() || reference == "NONREF" {
+            bank_reference
+        } else {
+            reference
+        };
+
+        let (text, counter_account) = parse_information(info);
+
+        transactions.push(Transaction {
+            interest_date: value_date,
+            archive_reference: reference.trim().to_string(),
+            counter_account,
+            types,
+            text,
+            currency: String::from(currency),
+            ..Transaction::new(accounting_date, amount)
+        });
+        Ok(())
+    }
+
+    // C|D YYMMDD currency amount, e.g. C220531NOK1000,00
+    fn get_balance(path: &str, location: &Location, value: &str) -> Result<bank::Balance, Error> {
+        let value = value.trim();
+        let bad_value =
+            |value: &str, expected: &str| Error::bad_value(path, location.clone(), value, expected);
+        if value.len() < 11 || !value.is_char_boundary(1) || !value.is_char_boundary(10) {
+            return Err(bad_value(value, "a balance"));
+        }
+        let date = yymmdd_to_date(&value[1..7])
+            .map_err(|_| Error::bad_date(path, location.clone(), &value[1..7]))?;
+        let amount = bank::parse_amount(&value[10..])
+            .map_err(|_| Error::bad_amount(path, location.clone(), &value[10..]))?;
+        let amount = match &value[..1] {
+            "C" => amount,
+            "D" => -amount,
+            mark => return Err(bad_value(mark, "a debit/credit mark")),
+        };
+        Ok(bank::Balance { date, amount })
+    }
+}
+
+// the currency of a balance, e.g. NOK in C220531NOK1000,00
+fn balance_currency(value: &str) -> String {
+    value.trim().chars().skip(7).take(3).collect()
+}
+
+// splits the statement into (line, tag, value), values can span several lines and the line
+// is the one the tag is on
+fn get_fields(content: &str) -> Vec<(usize, String, String)> {
+    let mut fields: Vec<(usize, String, String)> = vec![];
+    for (i, line) in content.lines().enumerate() {
+        let mut line = line.trim_end();
+        // the swift block headers and trailers are not part of the statement
+        if line.starts_with('{') || line == "-}" || line == "-" {
+            match line.find("{4:") {
+                Some(i) if i + 3 < line.len() => line = &line[i + 3..],
+                _ => continue,
+            };
+        }
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|l| l.find(':').map(|end| (&l[..end], &l[end + 1..])));
+        match tag {
+            Some((tag, value))
+                if !tag.is_empty()
+                    && tag.len() <= 3
+                    && tag.chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                fields.push((i + 1, tag.to_string(), value.to_string()))
+            }
+            _ => {
+                if let Some((_, _, value)) = fields.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+        }
+    }
+    fields
+}
+
+// the :86: field is either free text or structured subfields like ?20purpose?31account?32name
+fn parse_information(info: &str) -> (String, String) {
+    if !info.contains('?') {
+        let lines: Vec<&str> = info.lines().map(|l| l.trim()).collect();
+        return (lines.join(" ").trim().to_string(), String::new());
+    }
+    // subfields may be wrapped over several lines
+    let info = info.replace('\n', "");
+    let mut subfields: HashMap<String, String> = HashMap::new();
+    for part in info.split('?').skip(1) {
+        if part.len() >= 2 && part.is_char_boundary(2) {
+            subfields
+                .entry(part[..2].to_string())
+                .or_default()
+                .push_str(&part[2..]);
+        }
+    }
+    let mut purpose = vec![];
+    for code in 20..30 {
+        if let Some(value) = subfields.get(&code.to_string()) {
+            purpose.push(value.trim().to_string());
+        }
+    }
+    let name = [subfields.get("32"), subfields.get("33")]
+        .iter()
+        .flatten()
+        .map(|s| s.trim())
+        .collect::<Vec<&str>>()
+        .join("");
+    let text = if purpose.is_empty() {
+        name
+    } else if name.is_empty() {
+        purpose.join(" ")
+    } else {
+        format!("{} {}", name, purpose.join(" "))
+    };
+    let account = subfields
+        .get("31")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default();
+    (text, account)
+}
+
+fn yymmdd_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+    Ok(NaiveDate::parse_from_str(date, "%y%m%d")
+        .map_err(|e| format!("date str '{}' is not valid: {:?}", date, e))?)
+}
+
+// the entry date has no year, it is the year of the value date unless it crosses new year
+fn entry_date(value_date: NaiveDate, mmdd: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+    let parse = |year: i32| NaiveDate::parse_from_str(&format!("{}{}", year, mmdd), "%Y%m%d");
+    let date = parse(value_date.year())
+        .map_err(|e| format!("entry date '{}' is not valid: {:?}", mmdd, e))?;
+    let days = (date - value_date).num_days();
+    if days > 180 {
+        Ok(parse(value_date.year() - 1)?)
+    } else if days < -180 {
+        Ok(parse(value_date.year() + 1)?)
+    } else {
+        Ok(date)
+    }
+}
+
+#[cfg(test)]
+mod tests_mt940 {
+    use super::*;
+
+    const STATEMENT: &str = "{1:F01BANKNOKKAXXX0000000000}{2:I940BANKNOKKXXXXN}{4:\n\
+        :20:STARTUMS\n\
+        :25:12345678/0001234567\n\
+        :28C:00001/001\n\
+        :60F:C220531NOK1000,00\n\
+        :61:2206030602D112,50NTRFNONREF//BANK1\n\
+        :86:Kiwi AS\n\
+        Invoice 42\n\
+        :61:221231C500,NMSCREF2\n\
+        :86:166?20Salary?21December?31NO9386011117947?32Employer AS\n\
+        :61:2301011231RD12,50NTRFREF3\n\
+        :62F:C230101NOK1400,00\n\
+        -}";
+
+    #[test]
+    fn test_parse_str() -> Result<(), Box<dyn error::Error>> {
+        let statement = Mt940::parse_str("test.sta", STATEMENT)?;
+        let t = &statement.transactions;
+        assert_eq!(t.len(), 3);
+        assert_eq!(t[0].accounting_date, yymmdd_to_date("220602")?);
+        assert_eq!(t[0].interest_date, yymmdd_to_date("220603")?);
+        // the entry date is in the year before the value date
+        assert_eq!(t[2].accounting_date, yymmdd_to_date("221231")?);
+        assert_eq!(
+            t.column(|t| t.archive_reference.clone()),
+            vec!["BANK1", "REF2", "REF3"]
+        );
+        assert_eq!(t.column(|t| t.types.clone()), vec!["NTRF", "NMSC", "NTRF"]);
+        assert_eq!(t[0].text, "Kiwi AS Invoice 42");
+        assert_eq!(t[1].text, "Employer AS Salary December");
+        assert_eq!(t[1].counter_account, "NO9386011117947");
+        assert_eq!(t.column(|t| t.amount().to_f64()), vec![-112.5, 500.0, 12.5]);
+        assert_eq!(t[0].currency, "NOK");
+
+        assert_eq!(statement.opening_balance.unwrap().amount.to_f64(), 1000.0);
+        assert_eq!(statement.closing_balance.unwrap().amount.to_f64(), 1400.0);
+        assert!(statement.reconcile()?.is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_str_not_mt940() {
+        let error = Mt940::parse_str("test.sta", "not a statement").unwrap_err();
+        assert_eq!(error.to_string(), "test.sta is not a MT940 statement");
+    }
+
+    #[test]
+    fn test_parse_str_bad_line() -> Result<(), Box<dyn error::Error>> {
+        let content = STATEMENT.replace(":61:221231C500,", ":61:221231X500,");
+        let error = Mt940::parse_str("test.sta", &content).unwrap_err();
+        assert_eq!(
+            *error.downcast::<Error>()?,
+            Error::bad_value(
+                "test.sta",
+                Location::line(9, ""),
+                "X",
+                "a debit/credit mark"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_balance() {
+        let location = Location::line(5, "");
+        let balance = |value: &str| Mt940::get_balance("test.sta", &location, value);
+        assert_eq!(balance("D220531NOK10,5").unwrap().amount.ore(), -1050);
+        assert_eq!(
+            balance("X220531NOK10,5").unwrap_err().to_string(),
+            "test.sta: line 5: 'X' is not a debit/credit mark"
+        );
+        assert!(balance("C22").is_err());
+    }
+}
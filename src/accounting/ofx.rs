@@ -0,0 +1,315 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::error;
+use std::fs;
+
+use super::bank;
+use super::money::Money;
+use super::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
+
+// Reads OFX/QFX statements, both 1.x (SGML, closing tags are optional) and 2.x (XML)
+#[derive(Debug)]
+pub struct Ofx;
+
+impl bank::BankImporter for Ofx {
+    fn name(&self) -> &str {
+        "OFX / QFX"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ofx", "qfx"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Ofx::get_transactions(path)
+    }
+}
+
+impl Ofx {
+    pub fn get_transactions(path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        let bytes = fs::read(path).map_err(|e| Error::Io {
+            file: String::from(path),
+            reason: format!("{:?}", e),
+        })?;
+        // 1.x files are often latin-1
+        let content = match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => e.into_bytes().iter().map(|b| *b as char).collect(),
+        };
+        Ofx::parse_str(path, &content)
+    }
+
+    pub fn parse_str(path: &str, content: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        // the currency of the statement, the transactions are in it unless they say otherwise
+        let default_currency = first_value(content, "CURDEF").unwrap_or_default();
+        let mut transactions = Transactions::new();
+        for (line, record) in get_records(content, "STMTTRN") {
+            let field = |tag: &str| record.get(tag).cloned().unwrap_or_default();
+            // the errors point to the transaction, the leaves have no line of their own
+            let location = |tag: &str| Location::element(line, &format!("STMTTRN/{}", tag));
+
+            let accounting_date = Ofx::string_to_date(&field("DTPOSTED"))
+                .map_err(|_| Error::bad_date(path, location("DTPOSTED"), &field("DTPOSTED")))?;
+            // the date the user initiated the transaction, not always present
+            let interest_date = match record.get("DTUSER") {
+                Some(date) => Ofx::string_to_date(date)
+                    .map_err(|_| Error::bad_date(path, location("DTUSER"), date))?,
+                None => accounting_date,
+            };
+            let amount = bank::parse_amount(&field("TRNAMT"))
+                .map_err(|_| Error::bad_amount(path, location("TRNAMT"), &field("TRNAMT")))?;
+
+            let name = field("NAME");
+            let memo = field("MEMO");
+            let text = if memo.is_empty() || memo == name {
+                name
+            } else if name.is_empty() {
+                memo
+            } else {
+                format!("{} {}", name, memo)
+            };
+
+            let mut transaction = Transaction {
+                interest_date,
+                // FITID is unique per transaction, so repeated imports are recognized as duplicates
+                archive_reference: field("FITID"),
+                counter_account: field("ACCTID"),
+                types: field("TRNTYPE"),
+                text,
+                currency: default_currency.clone(),
+                ..Transaction::new(accounting_date, amount)
+            };
+            // CURRENCY means the amount is in that currency, ORIGCURRENCY means it was paid in
+            // that currency and the amount is in the currency of the statement, the rate is the
+            // value of one unit of it in the currency of the statement
+            let currency = field("CURRENCY/CURSYM");
+            let original_currency = field("ORIGCURRENCY/CURSYM");
+            if !currency.is_empty() {
+                transaction.currency = currency;
+            } else if !original_currency.is_empty() {
+                let rate = field("ORIGCURRENCY/CURRATE")
+                    .replace(',', ".")
+                    .parse::<f64>()
+                    .map_err(|_| {
+                        Error::bad_value(
+                            path,
+                            location("ORIGCURRENCY/CURRATE"),
+                            &field("ORIGCURRENCY/CURRATE"),
+                            "an exchange rate",
+                        )
+                    })?;
+                if rate > 0.0 {
+                    transaction.original_amount = Money::from_f64(amount.to_f64() / rate);
+                    transaction.original_currency = original_currency;
+                }
+            }
+            transactions.push(transaction);
+        }
+        Ok(transactions)
+    }
+
+    // OFX dates are on the form YYYYMMDD[HHMMSS[.XXX]][[-5:EST]], only the date is used
+    pub fn string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+        let day: String = date.trim().chars().take(8).collect();
+        Ok(NaiveDate::parse_from_str(&day, "%Y%m%d")
+            .map_err(|e| format!("date str is not valid: {:?}", e))?)
+    }
+}
+
+// collects the leaf elements of every <name> aggregate, e.g. every STMTTRN
+// works for both SGML (no closing tags on leaf elements) and XML, a leaf inside an
+// aggregate of the record is also found as "AGGREGATE/LEAF", e.g. "CURRENCY/CURSYM",
+// each record comes with the line it starts on
+pub fn get_records(content: &str, name: &str) -> Vec<(usize, HashMap<String, String>)> {
+    let mut records = vec![];
+    let mut record: Option<(usize, HashMap<String, String>)> = None;
+    // the aggregates inside the record the current element is in
+    let mut parents: Vec<String> = vec![];
+    // the last leaf, XML closes it, SGML does not
+    let mut leaf = String::new();
+
+    let mut rest = content;
+    let mut line = 1;
+    while let Some(start) = rest.find('<') {
+        line += rest[..start].matches('\n').count();
+        rest = &rest[start + 1..];
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = rest[..end].trim().to_uppercase();
+        let tag_line = line;
+        line += rest[..end].matches('\n').count();
+        rest = &rest[end + 1..];
+        // the value is the text up to the next tag
+        let value = match rest.find('<') {
+            Some(next) => &rest[..next],
+            None => rest,
+        };
+
+        if tag == name {
+            // SGML files may leave out the closing tag, the next record then closes it
+            if let Some(r) = record.replace((tag_line, HashMap::new())) {
+                records.push(r);
+            }
+            parents.clear();
+        } else if tag == format!("/{}", name) {
+            if let Some(r) = record.take() {
+                records.push(r);
+            }
+        } else if let Some((_, r)) = record.as_mut() {
+            let value = decode_entities(value.trim());
+            if let Some(closing) = tag.strip_prefix('/') {
+                if parents.last().is_some_and(|p| p == closing) {
+                    parents.pop();
+                } else if closing != leaf {
+                    // the closing tag of an aggregate around the record, e.g. </BANKTRANLIST>,
+                    // ends a record that SGML left open
+                    records.extend(record.take());
+                }
+            } else if value.is_empty() {
+                // aggregates, unlike leaves, always have a closing tag
+                parents.push(tag);
+            } else {
+                if let Some(parent) = parents.last() {
+                    r.insert(format!("{}/{}", parent, tag), value.clone());
+                }
+                leaf = tag.clone();
+                r.insert(tag, value);
+            }
+        }
+    }
+    // SGML files may also leave out the closing tag of the last aggregate
+    if let Some(r) = record {
+        records.push(r);
+    }
+    records
+}
+
+// the value of the first <tag> in the content, e.g. the currency of the statement
+fn first_value(content: &str, tag: &str) -> Option<String> {
+    // ascii upper case keeps the byte positions of the content
+    let start = content.to_ascii_uppercase().find(&format!("<{}>", tag))? + tag.len() + 2;
+    let value = &content[start..];
+    let end = value.find('<').unwrap_or(value.len());
+    Some(decode_entities(value[..end].trim())).filter(|value| !value.is_empty())
+}
+
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests_ofx {
+    use super::*;
+
+    #[test]
+    fn test_string_to_date() {
+        assert!(Ofx::string_to_date("20220603").is_ok());
+        assert!(Ofx::string_to_date("20220603120000.000[-5:EST]").is_ok());
+        assert!(Ofx::string_to_date("fail").is_err());
+    }
+
+    #[test]
+    fn test_parse_sgml() -> Result<(), Box<dyn error::Error>> {
+        let content = "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\n\n\
+            <OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><CURDEF>NOK\n<BANKTRANLIST>\n\
+            <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20220603120000\n<DTUSER>20220601\n\
+            <TRNAMT>-12.50\n<FITID>A1\n<NAME>KIWI\n<MEMO>card 1234\n\
+            <ORIGCURRENCY>\n<CURRATE>10.0\n<CURSYM>EUR\n</ORIGCURRENCY>\n</STMTTRN>\n\
+            <STMTTRN>\n<TRNTYPE>CREDIT\n<DTPOSTED>20220604\n<TRNAMT>100.00\n<FITID>A2\n\
+            <NAME>Salary &amp; bonus\n</STMTTRN>\n\
+            </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+        let transactions = Ofx::parse_str("test.ofx", content)?;
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions.column(|t| t.archive_reference.clone()),
+            vec!["A1", "A2"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["KIWI card 1234", "Salary & bonus"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.types.clone()),
+            vec!["DEBIT", "CREDIT"]
+        );
+        assert_eq!(
+            transactions[0].interest_date,
+            Ofx::string_to_date("20220601")?
+        );
+        assert_eq!(
+            transactions[1].interest_date,
+            transactions[1].accounting_date
+        );
+        assert_eq!(
+            transactions.column(|t| t.amount().to_f64()),
+            vec![-12.5, 100.0]
+        );
+        assert_eq!(
+            transactions.column(|t| t.currency.clone()),
+            vec!["NOK", "NOK"]
+        );
+        assert_eq!(transactions[0].original_currency, "EUR");
+        assert_eq!(transactions[0].original_amount, Money::from_ore(-125));
+        assert_eq!(transactions[1].original_currency, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_records_without_closing_tags() {
+        let content = "<BANKTRANLIST>\n\
+            <STMTTRN>\n<TRNTYPE>DEBIT\n<FITID>A1\n\
+            <STMTTRN>\n<TRNTYPE>CREDIT\n<FITID>A2\n\
+            <STMTTRN>\n<FITID>A3\n</BANKTRANLIST>\n\
+            <LEDGERBAL>\n<BALAMT>100.00\n<DTASOF>20220630\n</LEDGERBAL>";
+        let records = get_records(content, "STMTTRN");
+        let fitids: Vec<&str> = records.iter().map(|(_, r)| r["FITID"].as_str()).collect();
+        assert_eq!(fitids, vec!["A1", "A2", "A3"]);
+        let lines: Vec<usize> = records.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![2, 5, 8]);
+        assert_eq!(records[0].1["TRNTYPE"], "DEBIT");
+        assert!(!records[2].1.contains_key("TRNTYPE"));
+        // the balance after the list is not part of the last record
+        assert_eq!(records[2].1.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_xml() -> Result<(), Box<dyn error::Error>> {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <?OFX OFXHEADER="200" VERSION="220"?>
+            <OFX><CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS><BANKTRANLIST>
+            <STMTTRN><TRNTYPE>POS</TRNTYPE><DTPOSTED>20220603</DTPOSTED>
+            <TRNAMT>-5.25</TRNAMT><FITID>X9</FITID><NAME>Coffee</NAME>
+            <CURRENCY><CURRATE>10.5</CURRATE><CURSYM>USD</CURSYM></CURRENCY></STMTTRN>
+            </BANKTRANLIST></CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1></OFX>"#;
+        let transactions = Ofx::parse_str("test.ofx", content)?;
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].archive_reference, "X9");
+        assert_eq!(transactions[0].text, "Coffee");
+        assert_eq!(transactions[0].out_of_account.ore(), 525);
+        // no CURDEF, the amount is in the currency of the transaction
+        assert_eq!(transactions[0].currency, "USD");
+        assert_eq!(transactions[0].original_currency, "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let content = "<BANKTRANLIST>\n\
+            <STMTTRN>\n<DTPOSTED>20220603\n<TRNAMT>-12.50\n</STMTTRN>\n\
+            <STMTTRN>\n<DTPOSTED>20220604\n<TRNAMT>12.5o\n</STMTTRN>\n\
+            </BANKTRANLIST>";
+        let error = Ofx::parse_str("test.ofx", content).unwrap_err();
+        assert_eq!(
+            *error.downcast::<Error>().unwrap(),
+            Error::bad_amount("test.ofx", Location::element(6, "STMTTRN/TRNAMT"), "12.5o")
+        );
+    }
+}
@@ -0,0 +1,607 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fs;
+use std::path::Path;
+
+use super::bank;
+use super::money::Money;
+use super::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
+use crate::file;
+
+// the directory the GUI looks for user defined profiles in
+pub const PROFILE_DIR: &str = "profiles";
+
+// Which column (e.g. "A") holds each field of a transaction
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProfileColumns {
+    pub accounting_date: String,
+    // falls back to the accounting date if not set
+    pub interest_date: Option<String>,
+    pub archive_reference: Option<String>,
+    pub counter_account: Option<String>,
+    pub types: Option<String>,
+    pub text: Option<String>,
+    // either one signed amount column, or separate out of account and into account columns
+    pub amount: Option<String>,
+    pub out_of_account: Option<String>,
+    pub into_account: Option<String>,
+    // the currency of the amounts, the currency of the account if not set
+    pub currency: Option<String>,
+    // card statements often show what was paid in another currency next to the booked amount
+    pub original_amount: Option<String>,
+    pub original_currency: Option<String>,
+}
+
+impl ProfileColumns {
+    // the name and column of every field that has a column
+    fn set_columns(&self) -> Vec<(&'static str, &str)> {
+        let mut columns = vec![("accounting date", self.accounting_date.as_str())];
+        for (name, column) in [
+            ("interest date", &self.interest_date),
+            ("archive reference", &self.archive_reference),
+            ("counter account", &self.counter_account),
+            ("type", &self.types),
+            ("text", &self.text),
+            ("amount", &self.amount),
+            ("out of account", &self.out_of_account),
+            ("into account", &self.into_account),
+            ("currency", &self.currency),
+            ("original amount", &self.original_amount),
+            ("original currency", &self.original_currency),
+        ] {
+            if let Some(column) = column {
+                columns.push((name, column.as_str()));
+            }
+        }
+        columns
+    }
+}
+
+// Describes the layout of a bank export, so new banks can be imported without a hardcoded parser
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ImportProfile {
+    pub name: String,
+    // the worksheet holding the transactions, only used for xlsx files
+    pub sheet_name: String,
+    // if set, the export is read as a csv file separated by this character
+    pub delimiter: Option<char>,
+    // the first row (1-indexed) containing a transaction
+    pub first_row: u32,
+    pub columns: ProfileColumns,
+    // chrono format, e.g. "%d.%m.%Y"
+    pub date_format: String,
+    // the other of '.' and ',' can only be a thousands separator, if not set either one is
+    // read as the decimal separator and there is no thousands separator
+    pub decimal_separator: Option<char>,
+}
+
+impl Default for ImportProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            sheet_name: String::new(),
+            delimiter: None,
+            first_row: 2,
+            columns: ProfileColumns::default(),
+            date_format: String::from("%d.%m.%Y"),
+            decimal_separator: Some(','),
+        }
+    }
+}
+
+impl bank::BankImporter for ImportProfile {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extensions(&self) -> &[&str] {
+        match self.delimiter {
+            Some(_) => &["csv", "txt"],
+            None => &["xlsx"],
+        }
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        self.get_transactions(path)
+    }
+
+    fn parse_lenient(
+        &self,
+        path: &str,
+    ) -> Result<(Transactions, Vec<bank::SkippedRow>), Box<dyn error::Error>> {
+        bank::collect_rows(self.read_rows(path)?, true)
+    }
+
+    fn skips_rows(&self) -> bool {
+        true
+    }
+}
+
+impl ImportProfile {
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn error::Error>> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("could not open profile: {:?}", e))?;
+        ImportProfile::from_toml(&content)
+            .map_err(|e| format!("profile '{}' is not valid: {}", path, e).into())
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, Box<dyn error::Error>> {
+        let profile: ImportProfile =
+            toml::from_str(content).map_err(|e| format!("could not read profile: {}", e))?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn error::Error>> {
+        if self.name.is_empty() {
+            return Err("the profile needs a name".into());
+        }
+        if self.delimiter.is_none() && self.sheet_name.is_empty() {
+            return Err("the profile needs a sheet name or a delimiter".into());
+        }
+        if self.first_row == 0 {
+            return Err("the first row starts on 1".into());
+        }
+        if self.columns.accounting_date.is_empty() {
+            return Err("the profile needs an accounting date column".into());
+        }
+        // an empty column would be read as column A
+        for (name, column) in self.columns.set_columns() {
+            let column = column.trim();
+            if column.is_empty() || !column.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(format!(
+                    "the {} column '{}' is not a column like \"A\"",
+                    name, column
+                )
+                .into());
+            }
+        }
+        let separate = self.columns.out_of_account.is_some() && self.columns.into_account.is_some();
+        if self.columns.amount.is_none() && !separate {
+            return Err(
+                "the profile needs an amount column or both out of account and into account columns"
+                    .into(),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn sbanken() -> Self {
+        Self {
+            name: String::from("SBanken"),
+            sheet_name: String::from("Kontoutskrift"),
+            delimiter: None,
+            first_row: 4,
+            columns: ProfileColumns {
+                accounting_date: String::from("A"),
+                interest_date: Some(String::from("B")),
+                archive_reference: Some(String::from("C")),
+                counter_account: Some(String::from("D")),
+                types: Some(String::from("E")),
+                text: Some(String::from("F")),
+                amount: None,
+                out_of_account: Some(String::from("G")),
+                into_account: Some(String::from("H")),
+                ..ProfileColumns::default()
+            },
+            date_format: String::from("%d.%m.%Y"),
+            decimal_separator: Some('.'),
+        }
+    }
+
+    pub fn dnb() -> Self {
+        Self {
+            name: String::from("DNB"),
+            sheet_name: String::from("Transaksjoner"),
+            delimiter: None,
+            first_row: 2,
+            columns: ProfileColumns {
+                accounting_date: String::from("A"),
+                interest_date: Some(String::from("C")),
+                archive_reference: Some(String::from("G")),
+                counter_account: Some(String::from("F")),
+                types: Some(String::from("E")),
+                text: Some(String::from("B")),
+                amount: Some(String::from("D")),
+                ..ProfileColumns::default()
+            },
+            date_format: String::from("%Y-%m-%d"),
+            // the amounts are written as "1234,56" or "1234.56", without thousands
+            decimal_separator: None,
+        }
+    }
+
+    pub fn get_transactions(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Ok(bank::collect_rows(self.read_rows(path)?, false)?.0)
+    }
+
+    fn read_rows(&self, path: &str) -> Result<bank::ReadRows, Box<dyn error::Error>> {
+        let rows = match self.delimiter {
+            Some(delimiter) => file::lib::read_csv(path, delimiter)?,
+            None => self.read_sheet(path)?,
+        };
+
+        let mut read = bank::ReadRows::new();
+        let columns = &self.columns;
+        for (line, row) in rows
+            .iter()
+            .skip_while(|(line, _)| *line < self.first_row as usize)
+        {
+            let line = *line;
+            let field = |col: &Option<String>| match col {
+                Some(col) => row.get(column_index(col)).cloned().unwrap_or_default(),
+                None => String::new(),
+            };
+
+            let accounting_date_str = field(&Some(columns.accounting_date.clone()));
+            // if the accounting date is empty, end of transactions
+            if accounting_date_str.is_empty() {
+                break;
+            }
+            // a csv file has no sheet or cells, so the line and the column tell where it is
+            let place = |col: &str| match self.delimiter {
+                Some(_) => Location::line(line, col),
+                None => Location::cell(&self.sheet_name, &format!("{}{}", col.trim(), line)),
+            };
+            let bad_date = |col: &str, value: String| Error::BadDate {
+                file: String::from(path),
+                location: place(col),
+                value,
+            };
+            let transaction = || -> Result<Transaction, Box<dyn error::Error>> {
+                let accounting_date = self
+                    .string_to_date(&accounting_date_str)
+                    .map_err(|_| bad_date(&columns.accounting_date, accounting_date_str.clone()))?;
+
+                let interest_date_str = field(&columns.interest_date);
+                let interest_date = match &columns.interest_date {
+                    Some(col) if !interest_date_str.is_empty() => self
+                        .string_to_date(&interest_date_str)
+                        .map_err(|_| bad_date(col, interest_date_str.clone()))?,
+                    _ => accounting_date,
+                };
+
+                let amount = |col: &Option<String>| {
+                    let value = field(col);
+                    self.parse_amount(&value).map_err(|_| Error::BadAmount {
+                        file: String::from(path),
+                        location: place(col.as_deref().unwrap_or_default()),
+                        value,
+                    })
+                };
+                let mut transaction = Transaction {
+                    interest_date,
+                    archive_reference: field(&columns.archive_reference),
+                    counter_account: field(&columns.counter_account),
+                    types: field(&columns.types),
+                    text: field(&columns.text),
+                    currency: field(&columns.currency),
+                    ..Transaction::new(accounting_date, Money::ZERO)
+                };
+                if columns.amount.is_some() {
+                    transaction.set_amount(amount(&columns.amount)?);
+                } else {
+                    transaction.out_of_account = amount(&columns.out_of_account)?.abs();
+                    transaction.into_account = amount(&columns.into_account)?;
+                }
+                // the original amount has the direction of the booked amount
+                let original_currency = field(&columns.original_currency);
+                if !original_currency.is_empty() && !field(&columns.original_amount).is_empty() {
+                    let original_amount = amount(&columns.original_amount)?.abs();
+                    transaction.original_amount = if transaction.amount().is_negative() {
+                        -original_amount
+                    } else {
+                        original_amount
+                    };
+                    transaction.original_currency = original_currency;
+                }
+                Ok(transaction)
+            };
+            read.push((line, transaction()));
+        }
+        Ok(read)
+    }
+
+    // reads the sheet as rows of text, the same way the csv files are read
+    fn read_sheet(&self, path: &str) -> Result<file::lib::CsvRows, Box<dyn error::Error>> {
+        let book = file::lib::open_file(path)?;
+        let sheet = book
+            .get_sheet_by_name(&self.sheet_name)
+            .map_err(|_| Error::MissingSheet {
+                file: String::from(path),
+                sheet: self.sheet_name.clone(),
+            })?;
+        let date_columns: Vec<usize> = [
+            Some(self.columns.accounting_date.clone()),
+            self.columns.interest_date.clone(),
+        ]
+        .iter()
+        .flatten()
+        .map(|col| column_index(col))
+        .collect();
+
+        let (max_col, max_row) = sheet.get_highest_column_and_row();
+        let mut rows = vec![];
+        for row in 1..=max_row {
+            let mut values = vec![];
+            for col in 1..=max_col {
+                // dates are read as they are shown, so the date format applies
+                let value = if date_columns.contains(&(col as usize - 1)) {
+                    sheet.get_formatted_value_by_column_and_row(&col, &row)
+                } else {
+                    match sheet
+                        .get_cell_by_column_and_row(&col, &row)
+                        .map(|cell| cell.get_raw_value())
+                    {
+                        // numbers are written with the decimal separator of the profile,
+                        // so they are parsed the same way as numbers stored as text
+                        Some(umya_spreadsheet::CellRawValue::Numeric(n)) => n
+                            .to_string()
+                            .replace('.', &self.decimal_separator.unwrap_or('.').to_string()),
+                        _ => sheet.get_value_by_column_and_row(&col, &row),
+                    }
+                };
+                values.push(value);
+            }
+            rows.push((row as usize, values));
+        }
+        Ok(rows)
+    }
+
+    pub fn string_to_date(&self, date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+        Ok(NaiveDate::parse_from_str(date, &self.date_format)
+            .map_err(|e| format!("date str is not valid: {:?}", e))?)
+    }
+
+    pub fn parse_amount(&self, amount: &str) -> Result<Money, Box<dyn error::Error>> {
+        let decimal = match self.decimal_separator {
+            Some(decimal) => decimal,
+            None => return bank::parse_amount(amount),
+        };
+        // the other separator only separates groups of three digits before the decimals,
+        // e.g. "1234.56" in a profile with ',' is rejected instead of read as 123456
+        let thousands = if decimal == ',' { '.' } else { ',' };
+        let (whole, decimals) = amount.split_once(decimal).unwrap_or((amount, ""));
+        let groups: Vec<&str> = whole.trim().split(thousands).collect();
+        if decimals.contains(thousands)
+            || groups[1..]
+                .iter()
+                .any(|group| group.len() != 3 || !group.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(format!(
+                "'{}' does not have '{}' as the decimal separator",
+                amount, decimal
+            )
+            .into());
+        }
+        let s: String = amount.chars().filter(|c| *c != thousands).collect();
+        bank::parse_amount(&s)
+    }
+}
+
+// converts a column name like "A" or "AB" to a zero based index
+pub fn column_index(column: &str) -> usize {
+    column
+        .trim()
+        .to_uppercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .fold(0, |acc, c| acc * 26 + (c as usize - 'A' as usize + 1))
+        .saturating_sub(1)
+}
+
+// loads every profile (*.toml) in the directory, profiles that are not valid are skipped
+pub fn load_profiles(dir: &str) -> Vec<ImportProfile> {
+    let mut profiles = vec![];
+    let entries = match fs::read_dir(Path::new(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return profiles,
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("toml") {
+            if let Some(p) = path.to_str() {
+                if let Ok(profile) = ImportProfile::from_file(p) {
+                    profiles.push(profile);
+                }
+            }
+        }
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+#[cfg(test)]
+mod tests_profile {
+    use super::*;
+
+    #[test]
+    fn test_column_index() {
+        assert_eq!(column_index("A"), 0);
+        assert_eq!(column_index("h"), 7);
+        assert_eq!(column_index("AB"), 27);
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let profile = ImportProfile::from_toml(
+            r#"
+            name = "My bank"
+            delimiter = ";"
+            first_row = 2
+            date_format = "%Y-%m-%d"
+            decimal_separator = ","
+
+            [columns]
+            accounting_date = "A"
+            text = "B"
+            amount = "C"
+            "#,
+        );
+        assert!(profile.is_ok());
+        assert_eq!(profile.unwrap().columns.text, Some(String::from("B")));
+
+        // no amount column
+        let profile = ImportProfile::from_toml(
+            r#"
+            name = "My bank"
+            sheet_name = "Sheet1"
+
+            [columns]
+            accounting_date = "A"
+            "#,
+        );
+        assert!(profile.is_err());
+
+        // an empty column is not read as column A
+        let profile = ImportProfile::from_toml(
+            r#"
+            name = "My bank"
+            delimiter = ";"
+
+            [columns]
+            accounting_date = "A"
+            text = ""
+            amount = "C"
+            "#,
+        );
+        assert!(profile.is_err());
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        let profile = ImportProfile::default();
+        assert_eq!(
+            profile.parse_amount("-1.234,50").unwrap(),
+            Money::from_ore(-123450)
+        );
+        assert!(profile.parse_amount("1234.56").is_err());
+        let profile = ImportProfile::sbanken();
+        assert_eq!(
+            profile.parse_amount("1,234.50").unwrap(),
+            Money::from_ore(123450)
+        );
+        assert_eq!(
+            profile.parse_amount("1,234").unwrap(),
+            Money::from_kroner(1234)
+        );
+        assert_eq!(
+            profile.parse_amount("-1,234,567").unwrap(),
+            Money::from_kroner(-1234567)
+        );
+        // a ',' that does not separate thousands is not read as the decimal separator
+        assert!(profile.parse_amount("1,23").is_err());
+        assert!(profile.parse_amount("1.234,50").is_err());
+        let profile = ImportProfile::dnb();
+        assert_eq!(
+            profile.parse_amount("1234.56").unwrap(),
+            Money::from_ore(123456)
+        );
+        assert_eq!(
+            profile.parse_amount("-1234,56").unwrap(),
+            Money::from_ore(-123456)
+        );
+    }
+
+    #[test]
+    fn test_get_transactions_csv() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_profile.csv";
+        fs::write(
+            path,
+            "Date;Text;Amount;Currency;Original;Original currency\n\
+             2022-06-03;KIWI;-12,50;NOK;-1,25;EUR\n\
+             2022-06-04;Salary;1.000,00;NOK;;\n",
+        )?;
+        let profile = ImportProfile {
+            name: String::from("csv"),
+            delimiter: Some(';'),
+            first_row: 2,
+            columns: ProfileColumns {
+                accounting_date: String::from("A"),
+                text: Some(String::from("B")),
+                amount: Some(String::from("C")),
+                currency: Some(String::from("D")),
+                original_amount: Some(String::from("E")),
+                original_currency: Some(String::from("F")),
+                ..ProfileColumns::default()
+            },
+            date_format: String::from("%Y-%m-%d"),
+            ..ImportProfile::default()
+        };
+        let transactions = profile.get_transactions(path);
+        fs::remove_file(path)?;
+        let transactions = transactions?;
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["KIWI", "Salary"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.amount().to_f64()),
+            vec![-12.5, 1000.0]
+        );
+        assert_eq!(
+            transactions[0].interest_date,
+            transactions[0].accounting_date
+        );
+        assert_eq!(
+            transactions.column(|t| t.currency.clone()),
+            vec!["NOK", "NOK"]
+        );
+        assert_eq!(transactions[0].original_amount, Money::from_ore(-125));
+        assert_eq!(
+            transactions.column(|t| t.original_currency.clone()),
+            vec!["EUR", ""]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lenient() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_profile_lenient.csv";
+        fs::write(
+            path,
+            "Date;Text;Out;In\n2022-06-03;KIWI;12.50;0\n2022-13-04;Vipps;100.00;0\n",
+        )?;
+        let profile = ImportProfile {
+            delimiter: Some(';'),
+            first_row: 2,
+            columns: ProfileColumns {
+                accounting_date: String::from("A"),
+                text: Some(String::from("B")),
+                out_of_account: Some(String::from("C")),
+                into_account: Some(String::from("D")),
+                ..ProfileColumns::default()
+            },
+            date_format: String::from("%Y-%m-%d"),
+            ..ImportProfile::sbanken()
+        };
+        let strict = profile.get_transactions(path);
+        let lenient = bank::BankImporter::parse_lenient(&profile, path);
+        fs::remove_file(path)?;
+        assert_eq!(
+            *strict.unwrap_err().downcast::<Error>()?,
+            Error::BadDate {
+                file: String::from(path),
+                location: Location::line(3, "A"),
+                value: String::from("2022-13-04"),
+            }
+        );
+        let (transactions, skipped) = lenient?;
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(
+            skipped[0].to_string(),
+            "line 3: A: '2022-13-04' is not a date"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_profiles() {
+        assert_eq!(load_profiles("does_not_exist").len(), 0);
+    }
+}
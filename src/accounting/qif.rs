@@ -0,0 +1,370 @@
+use chrono::NaiveDate;
+use std::error;
+use std::fs;
+
+use super::bank;
+use super::money::Money;
+use super::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
+use crate::excel::reading;
+use crate::excel::schema::Schema;
+
+// Reads and writes Quicken Interchange Format files
+#[derive(Debug)]
+pub struct Qif;
+
+// the !Type sections that hold the transactions of an account, the other sections, e.g.
+// categories, classes and memorized transactions, use the same codes for other things
+const TRANSACTION_TYPES: &[&str] = &["bank", "cash", "ccard", "oth a", "oth l"];
+
+impl bank::BankImporter for Qif {
+    fn name(&self) -> &str {
+        "QIF"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["qif"]
+    }
+
+    fn parse(&self, path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        Qif::get_transactions(path)
+    }
+}
+
+impl Qif {
+    pub fn get_transactions(path: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        let bytes = fs::read(path).map_err(|e| Error::Io {
+            file: String::from(path),
+            reason: format!("{:?}", e),
+        })?;
+        // old programs write latin-1
+        let content = match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(e) => e.into_bytes().iter().map(|b| *b as char).collect(),
+        };
+        Qif::parse_str(path, &content)
+    }
+
+    pub fn parse_str(path: &str, content: &str) -> Result<Transactions, Box<dyn error::Error>> {
+        let mut transactions = Transactions::new();
+        let mut record = Record::default();
+        // a file without a header is read as a bank account
+        let mut in_transactions = true;
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (code, value) = line.split_at(line.chars().next().unwrap().len_utf8());
+            let value = value.trim();
+            match code {
+                // the header of a section, e.g. !Type:Bank or !Account, !Option and !Clear
+                // only switch a setting
+                "!" => {
+                    let header = value.to_lowercase();
+                    if !header.starts_with("option:") && !header.starts_with("clear:") {
+                        in_transactions = header
+                            .strip_prefix("type:")
+                            .is_some_and(|t| TRANSACTION_TYPES.contains(&t.trim()));
+                        record = Record::default();
+                    }
+                }
+                _ if !in_transactions => (),
+                "D" => {
+                    record.date = Some(
+                        Qif::string_to_date(value)
+                            .map_err(|_| Error::bad_date(path, Location::line(i + 1, ""), value))?,
+                    )
+                }
+                "T" | "U" => {
+                    record.amount =
+                        Some(Qif::parse_amount(value).map_err(|_| {
+                            Error::bad_amount(path, Location::line(i + 1, ""), value)
+                        })?)
+                }
+                "P" => record.payee = value.to_string(),
+                "M" => record.memo = value.to_string(),
+                "N" => record.number = value.to_string(),
+                "L" => record.category = value.to_string(),
+                "^" => {
+                    record.push(path, &mut transactions, i + 1)?;
+                    record = Record::default();
+                }
+                // splits (S, E, $), cleared status, addresses and so on are not used
+                _ => (),
+            }
+        }
+        // the last record does not always end with ^
+        if record.date.is_some() {
+            record.push(path, &mut transactions, content.lines().count())?;
+        }
+        Ok(transactions)
+    }
+
+    // QIF dates are either M/D/Y (us) or D.M.Y (european), the year can be 2 digits or 'YY
+    pub fn string_to_date(date: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+        let (parts, us): (Vec<&str>, bool) = if date.contains('/') {
+            (date.split(['/', '\'']).collect(), true)
+        } else {
+            (date.split(['.', '-']).collect(), false)
+        };
+        if parts.len() != 3 {
+            return Err(format!("date str '{}' is not valid", date).into());
+        }
+        let number = |s: &str| {
+            s.trim()
+                .parse::<u32>()
+                .map_err(|e| format!("date str '{}' is not valid: {:?}", date, e))
+        };
+        let (month, day) = if us {
+            (number(parts[0])?, number(parts[1])?)
+        } else {
+            (number(parts[1])?, number(parts[0])?)
+        };
+        let mut year = number(parts[2])? as i32;
+        // 'YY is used for 2000 and later by quicken
+        if parts[2].len() <= 2 {
+            year += if date.contains('\'') || year < 70 {
+                2000
+            } else {
+                1900
+            };
+        }
+        NaiveDate::from_ymd_opt(year, month, day)
+            .ok_or_else(|| format!("date str '{}' is not a valid date", date).into())
+    }
+
+    // amounts have the separators of the program that wrote them, e.g. "1,012.50" or
+    // "1.012,50", the last separator is the decimal one unless it separates more than one
+    // group of thousands, e.g. "1,012,000", a single separator followed by three digits could
+    // be either, so "1,012" is rejected
+    pub fn parse_amount(amount: &str) -> Result<Money, Box<dyn error::Error>> {
+        let is_separator = |c: char| c == '.' || c == ',';
+        let mut decimal = amount.rfind(is_separator);
+        if let Some(i) = decimal {
+            let last = amount.as_bytes()[i] as char;
+            if amount[i + 1..].trim().len() == 3 {
+                let other = amount[..i].contains(|c: char| is_separator(c) && c != last);
+                if amount.matches(last).count() > 1 {
+                    decimal = None;
+                } else if !other {
+                    return Err(format!(
+                        "'{}' could be a whole number or have three decimals",
+                        amount
+                    )
+                    .into());
+                }
+            }
+        }
+        let s: String = amount
+            .char_indices()
+            .filter(|(i, c)| !is_separator(*c) || Some(*i) == decimal)
+            .map(|(_, c)| c)
+            .collect();
+        bank::parse_amount(&s)
+    }
+}
+
+#[derive(Default)]
+struct Record {
+    date: Option<NaiveDate>,
+    amount: Option<Money>,
+    payee: String,
+    memo: String,
+    number: String,
+    category: String,
+}
+
+impl Record {
+    // the line is the one the record ends on
+    fn push(&self, path: &str, transactions: &mut Transactions, line: usize) -> Result<(), Error> {
+        let missing = |reason: &str| Error::bad_format(path, Location::line(line, ""), reason);
+        let date = self
+            .date
+            .ok_or_else(|| missing("the transaction has no date"))?;
+        let amount = self
+            .amount
+            .ok_or_else(|| missing("the transaction has no amount"))?;
+        let text = if self.memo.is_empty() {
+            self.payee.clone()
+        } else if self.payee.is_empty() {
+            self.memo.clone()
+        } else {
+            format!("{} {}", self.payee, self.memo)
+        };
+        // [Account] is a transfer to another account, not a category
+        let (counter_account, category) =
+            if self.category.starts_with('[') && self.category.ends_with(']') {
+                (
+                    self.category[1..self.category.len() - 1].to_string(),
+                    String::new(),
+                )
+            } else {
+                (String::new(), self.category.clone())
+            };
+
+        transactions.push(Transaction {
+            archive_reference: self.number.clone(),
+            counter_account,
+            text,
+            category,
+            ..Transaction::new(date, amount)
+        });
+        Ok(())
+    }
+}
+
+// writes the transactions as a bank account in QIF
+pub fn write_qif(transactions: &Transactions) -> String {
+    let mut qif = String::from("!Type:Bank\n");
+    for transaction in transactions {
+        qif.push_str(&format!(
+            "D{}\n",
+            transaction.accounting_date.format("%m/%d/%Y")
+        ));
+        qif.push_str(&format!("T{}\n", transaction.amount()));
+        qif.push_str(&format!("P{}\n", transaction.text));
+        if !transaction.types.is_empty() {
+            qif.push_str(&format!("M{}\n", transaction.types));
+        }
+        if !transaction.archive_reference.is_empty() {
+            qif.push_str(&format!("N{}\n", transaction.archive_reference));
+        }
+        if !transaction.category.is_empty() {
+            qif.push_str(&format!("L{}\n", transaction.category));
+        } else if !transaction.counter_account.is_empty() {
+            qif.push_str(&format!("L[{}]\n", transaction.counter_account));
+        }
+        qif.push_str("^\n");
+    }
+    qif
+}
+
+// exports the transactions of an account in the transaction sheet to a QIF file
+pub fn export(
+    workbook_path: &str,
+    schema: &Schema,
+    qif_path: &str,
+    account: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let transactions =
+        reading::get_workbook_transactions(workbook_path, schema)?.for_account(account);
+    fs::write(qif_path, write_qif(&transactions)).map_err(|e| Error::Write {
+        file: String::from(qif_path),
+        reason: format!("{:?}", e),
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_qif {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount() -> Result<(), Box<dyn error::Error>> {
+        assert_eq!(Qif::parse_amount("-1,012.50")?, Money::from_ore(-101250));
+        assert_eq!(Qif::parse_amount("-1.012,50")?, Money::from_ore(-101250));
+        assert_eq!(Qif::parse_amount("12,5")?, Money::from_ore(1250));
+        assert_eq!(Qif::parse_amount("1,012,000")?, Money::from_kroner(1012000));
+        assert_eq!(
+            Qif::parse_amount("1.012.000,50")?,
+            Money::from_ore(101200050)
+        );
+        // one separator and three digits is either a thousands group or three decimals
+        assert!(Qif::parse_amount("1,012").is_err());
+        assert!(Qif::parse_amount("0.125").is_err());
+        assert_eq!(Qif::parse_amount("500")?, Money::from_kroner(500));
+        assert!(Qif::parse_amount("fail").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_to_date() -> Result<(), Box<dyn error::Error>> {
+        let date = NaiveDate::from_ymd(2003, 6, 21);
+        assert_eq!(Qif::string_to_date("6/21/2003")?, date);
+        assert_eq!(Qif::string_to_date("6/21'03")?, date);
+        assert_eq!(Qif::string_to_date("06/21/03")?, date);
+        assert_eq!(Qif::string_to_date("21.06.2003")?, date);
+        assert_eq!(
+            Qif::string_to_date("12/31/98")?,
+            NaiveDate::from_ymd(1998, 12, 31)
+        );
+        assert!(Qif::string_to_date("21/06/2003").is_err());
+        assert!(Qif::string_to_date("fail").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_str() -> Result<(), Box<dyn error::Error>> {
+        let content = "!Type:Bank\n\
+            D6/21/2003\nT-1,012.50\nPKiwi\nMweekly shopping\nN1001\nLFood\n^\n\
+            D6/22/2003\nT500.00\nPSavings\nL[Savings account]\n^\n\
+            D6/23/2003\nT-20\nPSplit\nLCar\nSCar\n$-15\nSFood\n$-5\n";
+        let transactions = Qif::parse_str("test.qif", content)?;
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["Kiwi weekly shopping", "Savings", "Split"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.archive_reference.clone()),
+            vec!["1001", "", ""]
+        );
+        assert_eq!(
+            transactions.column(|t| t.counter_account.clone()),
+            vec!["", "Savings account", ""]
+        );
+        assert_eq!(
+            transactions.column(|t| t.category.clone()),
+            vec!["Food", "", "Car"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.amount().to_f64()),
+            vec![-1012.5, 500.0, -20.0]
+        );
+        assert_eq!(
+            Qif::parse_str("test.qif", "!Type:Bank\nPno date\n^\n")
+                .unwrap_err()
+                .to_string(),
+            "test.qif: line 3: the transaction has no date"
+        );
+        assert_eq!(
+            *Qif::parse_str("test.qif", "!Type:Bank\nD6/21/2003\nT12.5o\n^\n")
+                .unwrap_err()
+                .downcast::<Error>()?,
+            Error::bad_amount("test.qif", Location::line(3, ""), "12.5o")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_str_sections() -> Result<(), Box<dyn error::Error>> {
+        // a full export, only the bank and credit card sections are transactions
+        let content = "!Option:AutoSwitch\n!Account\nNChecking\nTBank\n^\n\
+            !Clear:AutoSwitch\n!Type:Cat\nNFood\nDGroceries\nE\n^\n\
+            !Type:Class\nNHome\nDHousehold\n^\n\
+            !Type:Bank\nD6/21/2003\nT-12.50\nPKiwi\n^\n\
+            !Type:Memorized\nKC\nT-50.00\nPRent\n^\n\
+            !Type:CCard\nD6/22/2003\nT-5.00\nPCoffee\n^\n";
+        let transactions = Qif::parse_str("test.qif", content)?;
+        assert_eq!(
+            transactions.column(|t| t.text.clone()),
+            vec!["Kiwi", "Coffee"]
+        );
+        assert_eq!(
+            transactions.column(|t| t.amount().to_f64()),
+            vec![-12.5, -5.0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_qif() -> Result<(), Box<dyn error::Error>> {
+        let content = "!Type:Bank\nD06/21/2003\nT-12.50\nPKiwi\nN1001\nLFood\n^\n\
+            D06/22/2003\nT500.00\nPSavings\nL[Savings account]\n^\n";
+        let transactions = Qif::parse_str("test.qif", content)?;
+        assert_eq!(write_qif(&transactions), content);
+        Ok(())
+    }
+}
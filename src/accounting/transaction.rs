@@ -0,0 +1,313 @@
+use chrono::NaiveDate;
+use std::ops::{Index, Range};
+use std::slice;
+use std::vec;
+
+use super::currency;
+use super::money::Money;
+
+// One row of the transaction sheet, "Kontoutskrift" by default
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub accounting_date: NaiveDate,
+    pub interest_date: NaiveDate,
+    pub archive_reference: String,
+    pub counter_account: String,
+    pub types: String,
+    pub text: String,
+    pub out_of_account: Money,
+    pub into_account: Money,
+    pub account: String,
+    // the currency of the amounts, empty if it is the currency of the account
+    pub currency: String,
+    // what was paid in another currency, e.g. a card payment in EUR on a NOK account, the
+    // original currency is empty if there is no such amount
+    pub original_amount: Money,
+    pub original_currency: String,
+    // the group if it is already known, e.g. the category of a qif export, else empty
+    pub category: String,
+}
+
+impl Transaction {
+    // the interest date is the same as the accounting date and the text fields are empty
+    pub fn new(accounting_date: NaiveDate, amount: Money) -> Self {
+        let mut transaction = Transaction {
+            accounting_date,
+            interest_date: accounting_date,
+            archive_reference: String::new(),
+            counter_account: String::new(),
+            types: String::new(),
+            text: String::new(),
+            out_of_account: Money::ZERO,
+            into_account: Money::ZERO,
+            account: String::new(),
+            currency: String::new(),
+            original_amount: Money::ZERO,
+            original_currency: String::new(),
+            category: String::new(),
+        };
+        transaction.set_amount(amount);
+        transaction
+    }
+
+    // positive when money comes into the account
+    pub fn amount(&self) -> Money {
+        self.into_account - self.out_of_account
+    }
+
+    // splits a signed amount into the out of account and into account columns
+    pub fn set_amount(&mut self, amount: Money) {
+        if amount.is_negative() {
+            self.out_of_account = -amount;
+            self.into_account = Money::ZERO;
+        } else {
+            self.out_of_account = Money::ZERO;
+            self.into_account = amount;
+        }
+    }
+
+    // the same transaction, the category is not compared since it can be changed in the workbook
+    pub fn is_duplicate(&self, other: &Transaction) -> bool {
+        self.duplicate_key() == other.duplicate_key()
+    }
+
+    // the fields that identify a transaction, with the text fields normalised so
+    // differences in case and whitespace between the workbook and the export do not matter
+    pub fn duplicate_key(&self) -> DuplicateKey {
+        DuplicateKey {
+            account: normalize_text(&self.account),
+            accounting_date: self.accounting_date,
+            interest_date: self.interest_date,
+            archive_reference: normalize_text(&self.archive_reference),
+            counter_account: normalize_text(&self.counter_account),
+            types: normalize_text(&self.types),
+            text: normalize_text(&self.text),
+            out_of_account: self.out_of_account,
+            into_account: self.into_account,
+            currency: currency::normalize(&self.currency),
+        }
+    }
+}
+
+// What remove_duplicates hashes to find a transaction that is already in the workbook
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DuplicateKey {
+    account: String,
+    accounting_date: NaiveDate,
+    interest_date: NaiveDate,
+    archive_reference: String,
+    counter_account: String,
+    types: String,
+    text: String,
+    out_of_account: Money,
+    into_account: Money,
+    currency: String,
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// The transactions of a bank export or a workbook, in order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transactions {
+    transactions: Vec<Transaction>,
+}
+
+impl Transactions {
+    pub fn new() -> Self {
+        Transactions::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub fn push(&mut self, transaction: Transaction) {
+        self.transactions.push(transaction);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Transaction> {
+        self.transactions.get(index)
+    }
+
+    pub fn first(&self) -> Option<&Transaction> {
+        self.transactions.first()
+    }
+
+    pub fn last(&self) -> Option<&Transaction> {
+        self.transactions.last()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Transaction> {
+        self.transactions.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, Transaction> {
+        self.transactions.iter_mut()
+    }
+
+    // the transactions matching the predicate, in the same order
+    pub fn filter<P>(&self, mut predicate: P) -> Transactions
+    where
+        P: FnMut(&Transaction) -> bool,
+    {
+        self.iter().filter(|t| predicate(t)).cloned().collect()
+    }
+
+    pub fn for_account(&self, account: &str) -> Transactions {
+        self.filter(|t| t.account == account)
+    }
+
+    // the transactions booked from the start of from to the end of to
+    pub fn between(&self, from: NaiveDate, to: NaiveDate) -> Transactions {
+        self.filter(|t| t.accounting_date >= from && t.accounting_date <= to)
+    }
+
+    // a copy of the transactions in the range, the range is cut to the length
+    pub fn slice(&self, range: Range<usize>) -> Transactions {
+        let end = range.end.min(self.len());
+        let start = range.start.min(end);
+        Transactions::from(self.transactions[start..end].to_vec())
+    }
+
+    // oldest first, transactions on the same date keep their order
+    pub fn sort_by_date(&mut self) {
+        self.transactions.sort_by_key(|t| t.accounting_date);
+    }
+
+    pub fn reverse(&mut self) {
+        self.transactions.reverse();
+    }
+
+    pub fn set_account(&mut self, account: &str) {
+        for transaction in self.iter_mut() {
+            transaction.account = String::from(account);
+        }
+    }
+
+    // the values of one column, e.g. every text
+    pub fn column<T, F>(&self, f: F) -> Vec<T>
+    where
+        F: Fn(&Transaction) -> T,
+    {
+        self.iter().map(f).collect()
+    }
+
+    // the sum of the amounts, positive when more came in than went out
+    pub fn sum(&self) -> Money {
+        self.iter().map(|t| t.amount()).sum()
+    }
+}
+
+impl From<Vec<Transaction>> for Transactions {
+    fn from(transactions: Vec<Transaction>) -> Self {
+        Transactions { transactions }
+    }
+}
+
+impl FromIterator<Transaction> for Transactions {
+    fn from_iter<I: IntoIterator<Item = Transaction>>(iter: I) -> Self {
+        Transactions::from(iter.into_iter().collect::<Vec<Transaction>>())
+    }
+}
+
+impl IntoIterator for Transactions {
+    type Item = Transaction;
+    type IntoIter = vec::IntoIter<Transaction>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.transactions.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Transactions {
+    type Item = &'a Transaction;
+    type IntoIter = slice::Iter<'a, Transaction>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.transactions.iter()
+    }
+}
+
+impl Index<usize> for Transactions {
+    type Output = Transaction;
+
+    fn index(&self, index: usize) -> &Transaction {
+        &self.transactions[index]
+    }
+}
+
+#[cfg(test)]
+mod tests_transaction {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2022, 6, day)
+    }
+
+    fn transactions() -> Transactions {
+        let mut transactions = Transactions::new();
+        for (day, amount, account) in [(3, -1000, "a"), (1, 500, "b"), (2, 250, "a")] {
+            let mut transaction = Transaction::new(date(day), Money::from_ore(amount));
+            transaction.account = String::from(account);
+            transactions.push(transaction);
+        }
+        transactions
+    }
+
+    #[test]
+    fn test_set_amount() {
+        let mut transaction = Transaction::new(date(1), Money::from_ore(-1250));
+        assert_eq!(transaction.out_of_account, Money::from_ore(1250));
+        assert_eq!(transaction.into_account, Money::ZERO);
+        transaction.set_amount(Money::from_kroner(3));
+        assert_eq!(transaction.out_of_account, Money::ZERO);
+        assert_eq!(transaction.amount(), Money::from_kroner(3));
+        assert_eq!(transaction.interest_date, transaction.accounting_date);
+    }
+
+    #[test]
+    fn test_is_duplicate() {
+        let transaction = Transaction::new(date(1), Money::from_ore(-1250));
+        let mut other = transaction.clone();
+        other.category = String::from("Food");
+        assert!(transaction.is_duplicate(&other));
+        other.currency = String::from("nok");
+        other.text = String::from("  ");
+        assert!(transaction.is_duplicate(&other));
+        other.text = String::from("text");
+        assert!(!transaction.is_duplicate(&other));
+    }
+
+    #[test]
+    fn test_filter_and_sort() {
+        let mut transactions = transactions();
+        assert_eq!(transactions.for_account("a").len(), 2);
+        assert_eq!(transactions.between(date(2), date(3)).len(), 2);
+        assert_eq!(transactions.between(date(1), date(1)).len(), 1);
+        assert_eq!(transactions.sum(), Money::from_ore(-250));
+
+        transactions.sort_by_date();
+        let days: Vec<NaiveDate> = transactions.iter().map(|t| t.accounting_date).collect();
+        assert_eq!(days, vec![date(1), date(2), date(3)]);
+        transactions.reverse();
+        assert_eq!(transactions[0].accounting_date, date(3));
+    }
+
+    #[test]
+    fn test_slice() {
+        let transactions = transactions();
+        assert_eq!(transactions.slice(1..3).len(), 2);
+        assert_eq!(transactions.slice(1..3)[0], transactions[1]);
+        assert_eq!(transactions.slice(2..10).len(), 1);
+        assert!(transactions.slice(5..10).is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use std::error;
+use std::fmt;
+
+// Where in a file a value is, a workbook has sheets and cells (e.g. "A5"), a text file has lines
+// and, when it has a header, named columns, and an xml or ofx file has elements
+#[derive(Debug, Clone, PartialEq)]
+pub enum Location {
+    Cell { sheet: String, cell: String },
+    Line { line: usize, column: String },
+    // the line the element, or the record it is part of, starts on
+    Element { line: usize, element: String },
+}
+
+impl Location {
+    pub fn cell(sheet: &str, cell: &str) -> Location {
+        Location::Cell {
+            sheet: String::from(sheet),
+            cell: String::from(cell),
+        }
+    }
+
+    pub fn line(line: usize, column: &str) -> Location {
+        Location::Line {
+            line,
+            column: String::from(column),
+        }
+    }
+
+    pub fn element(line: usize, element: &str) -> Location {
+        Location::Element {
+            line,
+            element: String::from(element),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Location::Cell { sheet, cell } => write!(f, "{}!{}", sheet, cell),
+            Location::Line { line, column } if column.is_empty() => write!(f, "line {}", line),
+            Location::Line { line, column } => write!(f, "line {}, {}", line, column),
+            Location::Element { line, element } => {
+                write!(f, "line {}, element {}", line, element)
+            }
+        }
+    }
+}
+
+// The errors of reading and writing workbooks and statements, each one knows the file and, when
+// there is one, the location it happened in
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    MissingSheet {
+        file: String,
+        sheet: String,
+    },
+    BadDate {
+        file: String,
+        location: Location,
+        value: String,
+    },
+    BadAmount {
+        file: String,
+        location: Location,
+        value: String,
+    },
+    BadAccountNumber {
+        file: String,
+        location: Location,
+        value: String,
+    },
+    // a value that is not a date or an amount, e.g. a debit/credit mark, `expected` says
+    // what it should have been
+    BadValue {
+        file: String,
+        location: Location,
+        value: String,
+        expected: String,
+    },
+    // the file is not laid out the way its format is, e.g. xml that is not closed
+    BadFormat {
+        file: String,
+        location: Location,
+        reason: String,
+    },
+    // the file is not a statement of the format it was read as, e.g. "camt.053"
+    NotStatement {
+        file: String,
+        format: String,
+    },
+    // the file could not be opened or read
+    Io {
+        file: String,
+        reason: String,
+    },
+    // the workbook could not be saved, the old workbook is left as it was
+    Write {
+        file: String,
+        reason: String,
+    },
+}
+
+impl Error {
+    pub fn bad_date(file: &str, location: Location, value: &str) -> Error {
+        Error::BadDate {
+            file: String::from(file),
+            location,
+            value: String::from(value),
+        }
+    }
+
+    pub fn bad_amount(file: &str, location: Location, value: &str) -> Error {
+        Error::BadAmount {
+            file: String::from(file),
+            location,
+            value: String::from(value),
+        }
+    }
+
+    pub fn bad_value(file: &str, location: Location, value: &str, expected: &str) -> Error {
+        Error::BadValue {
+            file: String::from(file),
+            location,
+            value: String::from(value),
+            expected: String::from(expected),
+        }
+    }
+
+    pub fn bad_format(file: &str, location: Location, reason: &str) -> Error {
+        Error::BadFormat {
+            file: String::from(file),
+            location,
+            reason: String::from(reason),
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        match self {
+            Error::MissingSheet { file, .. }
+            | Error::BadDate { file, .. }
+            | Error::BadAmount { file, .. }
+            | Error::BadAccountNumber { file, .. }
+            | Error::BadValue { file, .. }
+            | Error::BadFormat { file, .. }
+            | Error::NotStatement { file, .. }
+            | Error::Io { file, .. }
+            | Error::Write { file, .. } => file,
+        }
+    }
+
+    // the cell, line or element, if the error is about a place in the file
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::BadDate { location, .. }
+            | Error::BadAmount { location, .. }
+            | Error::BadAccountNumber { location, .. }
+            | Error::BadValue { location, .. }
+            | Error::BadFormat { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::MissingSheet { file, sheet } => {
+                write!(f, "{}: the sheet '{}' is missing", file, sheet)
+            }
+            Error::BadDate {
+                file,
+                location,
+                value,
+            } => write!(f, "{}: {}: '{}' is not a date", file, location, value),
+            Error::BadAmount {
+                file,
+                location,
+                value,
+            } => write!(f, "{}: {}: '{}' is not an amount", file, location, value),
+            Error::BadAccountNumber {
+                file,
+                location,
+                value,
+            } => write!(
+                f,
+                "{}: {}: '{}' is not an account number",
+                file, location, value
+            ),
+            Error::BadValue {
+                file,
+                location,
+                value,
+                expected,
+            } => write!(f, "{}: {}: '{}' is not {}", file, location, value, expected),
+            Error::BadFormat {
+                file,
+                location,
+                reason,
+            } => write!(f, "{}: {}: {}", file, location, reason),
+            Error::NotStatement { file, format } => {
+                write!(f, "{} is not a {} statement", file, format)
+            }
+            Error::Io { file, reason } => write!(f, "could not open {}: {}", file, reason),
+            Error::Write { file, reason } => write!(f, "could not save {}: {}", file, reason),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[cfg(test)]
+mod tests_error {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = Error::BadAmount {
+            file: String::from("budget.xlsx"),
+            location: Location::cell("Kontoutskrift", "G5"),
+            value: String::from("12,50"),
+        };
+        assert_eq!(
+            error.to_string(),
+            "budget.xlsx: Kontoutskrift!G5: '12,50' is not an amount"
+        );
+        assert_eq!(error.file(), "budget.xlsx");
+        assert_eq!(
+            error.location(),
+            Some(&Location::cell("Kontoutskrift", "G5"))
+        );
+        assert_eq!(
+            Error::bad_date(
+                "nordea.csv",
+                Location::line(3, "Bokføringsdato"),
+                "2022-13-01"
+            )
+            .to_string(),
+            "nordea.csv: line 3, Bokføringsdato: '2022-13-01' is not a date"
+        );
+
+        let boxed: Box<dyn error::Error> = Box::new(Error::MissingSheet {
+            file: String::from("budget.xlsx"),
+            sheet: String::from("Kategorier"),
+        });
+        assert!(boxed.downcast_ref::<Error>().is_some());
+        assert_eq!(
+            boxed.to_string(),
+            "budget.xlsx: the sheet 'Kategorier' is missing"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::accounting::transaction::Transaction;
+
+const DIRECTION: &str = "direction:";
+
+// How often each token was seen in the rows of one group
+#[derive(Debug, Clone, Default)]
+struct GroupCounts {
+    income_expense: String,
+    rows: usize,
+    tokens: HashMap<String, usize>,
+    total: usize,
+}
+
+// A naive Bayes classifier over the tokens of the text, type and counter account of the rows
+// that already have a group
+#[derive(Debug, Clone, Default)]
+pub struct Classifier {
+    // sorted, so the same rows always give the same guess
+    groups: BTreeMap<String, GroupCounts>,
+    vocabulary: HashSet<String>,
+    rows: usize,
+}
+
+// The most probable group of a transaction, confidence is the probability from 0 to 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guess {
+    pub group: String,
+    pub income_expense: String,
+    pub confidence: f64,
+}
+
+impl Classifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the number of rows learned from
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn learn(&mut self, transaction: &Transaction, group: &str, income_expense: &str) {
+        let counts = self.groups.entry(String::from(group)).or_default();
+        counts.income_expense = String::from(income_expense);
+        counts.rows += 1;
+        for token in tokens(transaction) {
+            *counts.tokens.entry(token.clone()).or_insert(0) += 1;
+            counts.total += 1;
+            self.vocabulary.insert(token);
+        }
+        self.rows += 1;
+    }
+
+    // none if nothing is learned or only the direction of the transaction has been seen before
+    pub fn guess(&self, transaction: &Transaction) -> Option<Guess> {
+        let known: Vec<String> = tokens(transaction)
+            .into_iter()
+            .filter(|token| self.vocabulary.contains(token))
+            .collect();
+        if known.iter().all(|token| token.starts_with(DIRECTION)) {
+            return None;
+        }
+
+        // log probabilities with add one smoothing, so an unseen token does not rule out a group
+        let vocabulary = self.vocabulary.len() as f64;
+        let scores: Vec<(&String, &GroupCounts, f64)> = self
+            .groups
+            .iter()
+            .map(|(group, counts)| {
+                let prior = (counts.rows as f64 / self.rows as f64).ln();
+                let likelihood: f64 = known
+                    .iter()
+                    .map(|token| {
+                        let count = counts.tokens.get(token).copied().unwrap_or(0) as f64;
+                        ((count + 1.0) / (counts.total as f64 + vocabulary)).ln()
+                    })
+                    .sum();
+                (group, counts, prior + likelihood)
+            })
+            .collect();
+
+        let (group, counts, best) = scores.iter().fold(
+            None,
+            |best: Option<&(&String, &GroupCounts, f64)>, score| match best {
+                Some(best) if best.2 >= score.2 => Some(best),
+                _ => Some(score),
+            },
+        )?;
+        // the probabilities are normalized relative to the best, to not underflow
+        let sum: f64 = scores
+            .iter()
+            .map(|(_, _, score)| (score - best).exp())
+            .sum();
+        Some(Guess {
+            group: String::from(*group),
+            income_expense: counts.income_expense.clone(),
+            confidence: 1.0 / sum,
+        })
+    }
+}
+
+// the words of the text, the type, the counter account and the direction of the amount, numbers in
+// the text are left out since they are mostly dates, card numbers and amounts
+pub fn tokens(transaction: &Transaction) -> Vec<String> {
+    let mut tokens: Vec<String> = transaction
+        .text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1 && !word.chars().any(|c| c.is_numeric()))
+        .map(|word| format!("text:{}", word))
+        .collect();
+    let types = transaction.types.trim().to_lowercase();
+    if !types.is_empty() {
+        tokens.push(format!("type:{}", types));
+    }
+    let counter_account = transaction.counter_account.trim();
+    if !counter_account.is_empty() {
+        tokens.push(format!("counter:{}", counter_account));
+    }
+    let direction = if transaction.amount().is_negative() {
+        "out"
+    } else {
+        "in"
+    };
+    tokens.push(format!("{}{}", DIRECTION, direction));
+    tokens
+}
+
+#[cfg(test)]
+mod tests_classifier {
+    use super::*;
+    use crate::accounting::money::Money;
+    use chrono::NaiveDate;
+
+    fn transaction(text: &str, types: &str, kroner: i64) -> Transaction {
+        Transaction {
+            text: String::from(text),
+            types: String::from(types),
+            ..Transaction::new(NaiveDate::from_ymd(2022, 6, 3), Money::from_kroner(kroner))
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        let mut kiwi = transaction("*4321 02.06 NOK 129.00 KIWI 505 BISLETT", "Varekjøp", -129);
+        kiwi.counter_account = String::from("12345678903");
+        assert_eq!(
+            tokens(&kiwi),
+            vec![
+                "text:nok",
+                "text:kiwi",
+                "text:bislett",
+                "type:varekjøp",
+                "counter:12345678903",
+                "direction:out"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guess() {
+        let mut classifier = Classifier::new();
+        assert_eq!(classifier.guess(&transaction("KIWI", "", -50)), None);
+        for (text, group) in [
+            ("KIWI BISLETT", "Food"),
+            ("KIWI MAJORSTUEN", "Food"),
+            ("REMA GRUNERLOKKA", "Food"),
+            ("RUTER BILLETT", "Transport"),
+            ("RUTER APP", "Transport"),
+        ] {
+            classifier.learn(&transaction(text, "Varekjøp", -50), group, "Expense");
+        }
+        classifier.learn(&transaction("LØNN", "Lønn", 30000), "Salary", "Income");
+        assert_eq!(classifier.rows(), 6);
+        assert_eq!(
+            classifier.guess(&transaction("VINMONOPOLET", "", -80)),
+            None
+        );
+
+        let kiwi = classifier
+            .guess(&transaction("KIWI TORSHOV", "Varekjøp", -80))
+            .unwrap();
+        assert_eq!(kiwi.group, "Food");
+        assert_eq!(kiwi.income_expense, "Expense");
+        let salary = classifier
+            .guess(&transaction("LØNN JUNI", "Lønn", 31000))
+            .unwrap();
+        assert_eq!(salary.group, "Salary");
+        assert_eq!(salary.income_expense, "Income");
+        assert!(salary.confidence > kiwi.confidence);
+
+        // a text with both a food and a transport word is less certain
+        let both = classifier
+            .guess(&transaction("KIWI RUTER", "Varekjøp", -80))
+            .unwrap();
+        assert!(both.confidence < kiwi.confidence);
+        assert!(both.confidence > 0.0 && kiwi.confidence <= 1.0);
+    }
+}
@@ -0,0 +1,131 @@
+use super::lib;
+use super::reading::Categories;
+use super::rules::Rule;
+use crate::accounting::transaction::{Transaction, Transactions};
+
+// A transaction that more than one group has a rule for
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    // the index of the transaction, e.g. the row in the workbook is the first row plus this
+    pub index: usize,
+    pub transaction: Transaction,
+    // one matching rule per group, in the order they are tried
+    pub rules: Vec<Rule>,
+    // the group categorize gives the transaction
+    pub chosen: String,
+}
+
+// A rule that never gives its group, because a rule of another group that wins over it
+// matches every transaction it matches
+#[derive(Debug, Clone)]
+pub struct Shadow {
+    pub rule: Rule,
+    pub by: Rule,
+}
+
+pub fn find_conflicts(transactions: &Transactions, categories: &Categories) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+    for (index, transaction) in transactions.iter().enumerate() {
+        let mut rules: Vec<Rule> = vec![];
+        for rule in categories
+            .all_rules()
+            .filter(|rule| rule.matches(transaction))
+        {
+            if !rules
+                .iter()
+                .any(|r| r.group.eq_ignore_ascii_case(&rule.group))
+            {
+                rules.push(rule.clone());
+            }
+        }
+        if rules.len() > 1 {
+            conflicts.push(Conflict {
+                index,
+                transaction: transaction.clone(),
+                rules,
+                chosen: lib::categorize(transaction, categories).0,
+            });
+        }
+    }
+    conflicts
+}
+
+pub fn find_shadowed(categories: &Categories) -> Vec<Shadow> {
+    let rules: Vec<&Rule> = categories.all_rules().collect();
+    let mut shadows = vec![];
+    for (i, rule) in rules.iter().enumerate() {
+        // the earlier rules win on the same priority, the later ones only on a higher one
+        let by = rules.iter().enumerate().find(|(j, other)| {
+            let wins =
+                other.priority > rule.priority || (other.priority == rule.priority && *j < i);
+            *j != i && wins && !other.group.eq_ignore_ascii_case(&rule.group) && other.covers(rule)
+        });
+        if let Some((_, by)) = by {
+            shadows.push(Shadow {
+                rule: (*rule).clone(),
+                by: (*by).clone(),
+            });
+        }
+    }
+    shadows
+}
+
+#[cfg(test)]
+mod tests_conflicts {
+    use super::*;
+    use crate::accounting::money::Money;
+    use chrono::NaiveDate;
+
+    fn two_groups(food: &[&str], transport: &[&str]) -> Categories {
+        let category = |group: &str, keywords: &[&str]| {
+            let mut category = vec![String::from(group), String::from("Expense")];
+            category.extend(keywords.iter().map(|k| String::from(*k)));
+            category
+        };
+        Categories::new(
+            vec![category("Food", food), category("Transport", transport)],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let categories = two_groups(&["kiwi"], &["circle k", "kiwi"]);
+        let mut transactions = Transactions::new();
+        for text in ["KIWI OSLO", "Circle K Oslo"] {
+            transactions.push(Transaction {
+                text: String::from(text),
+                ..Transaction::new(NaiveDate::from_ymd(2022, 6, 3), Money::from_kroner(-50))
+            });
+        }
+        let conflicts = find_conflicts(&transactions, &categories);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].index, 0);
+        assert_eq!(conflicts[0].rules.len(), 2);
+        assert_eq!(conflicts[0].chosen, "Food");
+
+        // a higher priority changes the chosen group
+        let categories = two_groups(&["kiwi"], &["kiwi; priority=1"]);
+        assert_eq!(
+            find_conflicts(&transactions, &categories)[0].chosen,
+            "Transport"
+        );
+    }
+
+    #[test]
+    fn test_find_shadowed() {
+        // "kiwi" is in every text "kiwi oslo" is in, and comes first
+        let shadows = find_shadowed(&two_groups(&["kiwi"], &["kiwi oslo", "esso"]));
+        assert_eq!(shadows.len(), 1);
+        assert_eq!(shadows[0].rule.group, "Transport");
+        assert_eq!(shadows[0].by.group, "Food");
+
+        // a higher priority or a narrower condition is not shadowed
+        assert!(find_shadowed(&two_groups(&["kiwi"], &["kiwi oslo; priority=1"])).is_empty());
+        assert!(find_shadowed(&two_groups(&["kiwi; out"], &["kiwi oslo"])).is_empty());
+        assert_eq!(
+            find_shadowed(&two_groups(&["kiwi; <100"], &["kiwi oslo; <=50"])).len(),
+            1
+        );
+    }
+}
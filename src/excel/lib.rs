@@ -1,6 +1,9 @@
 use super::reading;
-use super::workbook;
+use super::rules::Rule;
+use crate::accounting::money::Money;
+use crate::accounting::transaction::{DuplicateKey, Transaction, Transactions};
 use chrono::NaiveDate;
+use std::collections::HashMap;
 use std::error;
 
 pub fn get_month(
@@ -71,76 +74,240 @@ pub fn string_to_date(date: &str, delimiter: &str) -> Result<NaiveDate, Box<dyn
         .map_err(|e| format!("date str is not valid: {:?}", e))?)
 }
 
+// excel stores dates as the number of days since 1899-12-30
+pub fn excel_epoch() -> NaiveDate {
+    NaiveDate::from_ymd(1899, 12, 30)
+}
+
+pub fn date_to_serial(date: NaiveDate) -> i64 {
+    (date - excel_epoch()).num_days()
+}
+
+pub fn serial_to_date(serial: f64) -> Result<NaiveDate, Box<dyn error::Error>> {
+    // the fraction is the time of the day
+    excel_epoch()
+        .checked_add_signed(chrono::Duration::days(serial.floor() as i64))
+        .ok_or_else(|| format!("{} is not a valid excel date", serial).into())
+}
+
+// the value of a date cell, either an excel date or text like "03.06.2022" from older workbooks
+pub fn cell_to_date(value: &str) -> Result<NaiveDate, Box<dyn error::Error>> {
+    match value.trim().parse::<f64>() {
+        Ok(serial) => serial_to_date(serial),
+        Err(_) => string_to_date(value, &get_delimiter(value)),
+    }
+}
+
+// the number format of the date cells, e.g. dd.mm.yyyy
+pub fn date_format(delimiter: &str) -> String {
+    format!("dd{d}mm{d}yyyy", d = delimiter)
+}
+
+// the number format of the amount cells, with the currency after the amount
+pub fn amount_format(currency: &str) -> String {
+    format!("#,##0.00 \"{}\"", currency)
+}
+
 // checks if all vectors are the same length
 pub fn all_same_length<T>(vec: &Vec<&Vec<T>>) -> bool {
     vec.iter().all(|ref v| v.len() == vec[0].len())
 }
 
-pub fn remove_duplicates(
-    wb1: workbook::WorkbookInfo,
-    wb2: workbook::WorkbookInfo,
-) -> workbook::WorkbookInfo {
+pub fn remove_duplicates(wb1: Transactions, wb2: Transactions) -> Transactions {
     // Want to return wb1 - wb2
-    let mut wb = workbook::WorkbookInfo {
-        accounting_date: vec![],
-        interest_date: vec![],
-        archive_reference: vec![],
-        counter_account: vec![],
-        types: vec![],
-        text: vec![],
-        out_of_account: vec![],
-        into_account: vec![],
-        account: vec![],
-    };
+    split_duplicates(wb1, wb2).0
+}
 
-    for i in 0..wb1.accounting_date.len() {
-        let mut same = false;
-        for j in 0..wb2.accounting_date.len() {
-            // check if the attributes are the same!
-            // could replace all if with one if and 'or' (||)
-            if wb1.account[i] == wb2.account[j]
-                && wb1.accounting_date[i] == wb2.accounting_date[j]
-                && wb1.interest_date[i] == wb2.interest_date[j]
-                && wb1.archive_reference[i] == wb2.archive_reference[j]
-                && wb1.counter_account[i] == wb2.counter_account[j]
-                && wb1.types[i] == wb2.types[j]
-                && wb1.text[i] == wb2.text[j]
-                && wb1.out_of_account[i] == wb2.out_of_account[j]
-                && wb1.into_account[i] == wb2.into_account[j]
-            {
-                same = true;
-                break;
-            };
-        }
-        if !same {
-            wb.accounting_date.push(wb1.accounting_date[i]);
-            wb.interest_date.push(wb1.interest_date[i]);
-            wb.archive_reference.push(wb1.archive_reference[i].clone());
-            wb.counter_account.push(wb1.counter_account[i].clone());
-            wb.types.push(wb1.types[i].clone());
-            wb.text.push(wb1.text[i].clone());
-            wb.out_of_account.push(wb1.out_of_account[i]);
-            wb.into_account.push(wb1.into_account[i]);
-            wb.account.push(wb1.account[i].clone());
+// splits wb1 into the transactions that are not in wb2 and the ones that are
+pub fn split_duplicates(wb1: Transactions, wb2: Transactions) -> (Transactions, Transactions) {
+    // identical transactions can happen, e.g. two coffees on the same day, so the n-th
+    // occurrence in wb1 is only a duplicate if wb2 has the transaction at least n times
+    let mut in_workbook: HashMap<DuplicateKey, usize> = HashMap::new();
+    for transaction in &wb2 {
+        *in_workbook.entry(transaction.duplicate_key()).or_insert(0) += 1;
+    }
+    let mut occurrences: HashMap<DuplicateKey, usize> = HashMap::new();
+    let mut new = Transactions::new();
+    let mut duplicates = Transactions::new();
+    for transaction in wb1 {
+        let key = transaction.duplicate_key();
+        let occurrence = occurrences.entry(key.clone()).or_insert(0);
+        *occurrence += 1;
+        if *occurrence > in_workbook.get(&key).copied().unwrap_or(0) {
+            new.push(transaction);
+        } else {
+            duplicates.push(transaction);
         }
     }
-    wb
+    (new, duplicates)
 }
 
-pub fn get_category(text: &str, types: &str, categories: &reading::Categories) -> (String, String) {
-    for cat in &categories.from_type {
-        if types.to_lowercase().contains(&cat[0].to_lowercase()) {
-            return (String::from(&cat[0]), String::from(&cat[1]));
+// how alike two texts must be for a transaction to be a probable duplicate
+pub const TEXT_SIMILARITY: f64 = 0.6;
+
+// A new transaction that looks like one already in the workbook, e.g. a pending transaction
+// the bank exported again with a slightly different text or interest date
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suspect {
+    // the index of the transaction in the new transactions
+    pub index: usize,
+    pub existing: Transaction,
+    pub similarity: f64,
+}
+
+// finds the new transactions with the same account and amount as a workbook row, booked
+// at most days apart and with a similar text, exact duplicates should be removed first, a
+// workbook row identical to the transaction is not a candidate since split_duplicates has
+// already found the transaction to be a repeat of it
+pub fn find_suspects(wb1: &Transactions, wb2: &Transactions, days: i64) -> Vec<Suspect> {
+    let mut by_amount: HashMap<(String, Money, Money), Vec<&Transaction>> = HashMap::new();
+    for transaction in wb2 {
+        by_amount
+            .entry((
+                transaction.account.clone(),
+                transaction.out_of_account,
+                transaction.into_account,
+            ))
+            .or_default()
+            .push(transaction);
+    }
+
+    let mut suspects = vec![];
+    for (index, transaction) in wb1.iter().enumerate() {
+        let key = (
+            transaction.account.clone(),
+            transaction.out_of_account,
+            transaction.into_account,
+        );
+        let candidates = match by_amount.get(&key) {
+            Some(candidates) => candidates,
+            None => continue,
         };
+        let duplicate_key = transaction.duplicate_key();
+        let best = candidates
+            .iter()
+            .filter(|c| c.duplicate_key() != duplicate_key)
+            .filter(|c| {
+                (c.accounting_date - transaction.accounting_date)
+                    .num_days()
+                    .abs()
+                    <= days
+            })
+            .map(|c| (c, text_similarity(&transaction.text, &c.text)))
+            .filter(|(_, similarity)| *similarity >= TEXT_SIMILARITY)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        if let Some((existing, similarity)) = best {
+            suspects.push(Suspect {
+                index,
+                existing: (*existing).clone(),
+                similarity,
+            });
+        }
+    }
+    suspects
+}
+
+// 1.0 for the same text, the best of the edit distance and the share of the words
+// in the shorter text that are also in the longer one, e.g. when the bank adds a card number
+pub fn text_similarity(a: &str, b: &str) -> f64 {
+    let a = a.to_lowercase();
+    let b = b.to_lowercase();
+    let a_words: Vec<&str> = a.split_whitespace().collect();
+    let b_words: Vec<&str> = b.split_whitespace().collect();
+    if a_words == b_words {
+        return 1.0;
+    }
+    if a_words.is_empty() || b_words.is_empty() {
+        return 0.0;
+    }
+
+    let (shorter, longer) = if a_words.len() <= b_words.len() {
+        (&a_words, &b_words)
+    } else {
+        (&b_words, &a_words)
+    };
+    let common = shorter.iter().filter(|w| longer.contains(w)).count();
+    let word_similarity = common as f64 / shorter.len() as f64;
+
+    let a: Vec<char> = a_words.join(" ").chars().collect();
+    let b: Vec<char> = b_words.join(" ").chars().collect();
+    let edit_similarity = 1.0 - edit_distance(&a, &b) as f64 / a.len().max(b.len()) as f64;
+
+    word_similarity.max(edit_similarity)
+}
+
+// the levenshtein distance
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// the group and income/expense of a transaction, the category from the export is used if it has one
+pub fn transaction_category(
+    transaction: &Transaction,
+    categories: &reading::Categories,
+) -> (String, String) {
+    if transaction.category.is_empty() {
+        categorize(transaction, categories)
+    } else {
+        (
+            transaction.category.clone(),
+            get_income_expense(&transaction.category, categories),
+        )
     }
-    for cat in &categories.from_text {
-        for i in 2..cat.len() {
-            if text.to_lowercase().contains(&cat[i].to_lowercase()) {
-                return (String::from(&cat[0]), String::from(&cat[1]));
-            }
+}
+
+// the group and income/expense of the matching rule with the highest priority, on a tie the
+// rules file wins over the type categories, and they win over the key words of the text
+// categories
+pub fn categorize(transaction: &Transaction, categories: &reading::Categories) -> (String, String) {
+    match winning_rule(transaction, categories) {
+        Some(rule) => (rule.group.clone(), rule.income_expense.clone()),
+        None => (String::new(), String::new()),
+    }
+}
+
+pub fn winning_rule<'a>(
+    transaction: &Transaction,
+    categories: &'a reading::Categories,
+) -> Option<&'a Rule> {
+    let mut winner: Option<&Rule> = None;
+    for rule in categories.all_rules() {
+        if rule.matches(transaction) && winner.is_none_or(|w| rule.priority > w.priority) {
+            winner = Some(rule);
         }
     }
-    (String::new(), String::new())
+    winner
+}
+
+// the category from only the text and type, rules with an amount, account or direction
+// see a transaction of 0 kr without an account
+pub fn get_category(text: &str, types: &str, categories: &reading::Categories) -> (String, String) {
+    let transaction = Transaction {
+        text: String::from(text),
+        types: String::from(types),
+        ..Transaction::new(excel_epoch(), Money::ZERO)
+    };
+    categorize(&transaction, categories)
+}
+
+// finds the income/expense of a group in the categories sheet, empty if the group is not there
+pub fn get_income_expense(group: &str, categories: &reading::Categories) -> String {
+    categories
+        .from_type
+        .iter()
+        .chain(categories.from_text.iter())
+        .find(|cat| cat[0].to_lowercase() == group.to_lowercase())
+        .map(|cat| cat[1].clone())
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -202,6 +369,56 @@ mod tests_excel_lib {
         );
     }
 
+    #[test]
+    fn test_categorize() -> Result<(), Box<dyn error::Error>> {
+        let texts = vec![vec![
+            String::from("Food"),
+            String::from("Expense"),
+            String::from("re:^kiwi\\b"),
+            String::from("vipps"),
+        ]];
+        let rules = super::super::rules::rules_from_toml(
+            "[[rules]]\ngroup = \"Dining\"\nincome_expense = \"Expense\"\n\
+             text = \"vipps\"\nmax_amount = 200.0\ndirection = \"out\"\n",
+        )?;
+        let categories = reading::Categories::new(texts, vec![]).with_rules(rules);
+        let transaction = |text: &str, amount: i64| Transaction {
+            text: String::from(text),
+            ..Transaction::new(NaiveDate::from_ymd(2022, 6, 3), Money::from_kroner(amount))
+        };
+        let group = |text: &str, amount: i64| categorize(&transaction(text, amount), &categories).0;
+        assert_eq!(group("KIWI 123", -50), "Food");
+        assert_eq!(group("KIWIMARKET", -50), "");
+        // the rules file before the key words of the sheet
+        assert_eq!(group("Vipps Ola", -150), "Dining");
+        assert_eq!(group("Vipps Ola", -250), "Food");
+
+        // a key word with a higher priority wins over the rules file and the type categories
+        let texts = vec![vec![
+            String::from("Gifts"),
+            String::from("Expense"),
+            String::from("vipps; priority=2"),
+        ]];
+        let types = vec![vec![String::from("Vipps"), String::from("Expense")]];
+        let categories = reading::Categories::new(texts, types);
+        let transaction = Transaction {
+            types: String::from("Vipps"),
+            ..transaction("Vipps Ola", -150)
+        };
+        assert_eq!(categorize(&transaction, &categories).0, "Gifts");
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_income_expense() {
+        let texts = vec![vec![String::from("Food"), String::from("Expense")]];
+        let types = vec![vec![String::from("Salary"), String::from("Income")]];
+        let categories = reading::Categories::new(texts, types);
+        assert_eq!(get_income_expense("food", &categories), "Expense");
+        assert_eq!(get_income_expense("Salary", &categories), "Income");
+        assert_eq!(get_income_expense("Other", &categories), "");
+    }
+
     #[test]
     fn test_all_same_length() {
         let vec1 = vec![1, 2, 3];
@@ -220,106 +437,134 @@ mod tests_excel_lib {
         assert_eq!(date_to_string(date, "/"), "01/01/2022");
     }
 
+    #[test]
+    fn test_cell_to_date() -> Result<(), Box<dyn error::Error>> {
+        let date = NaiveDate::from_ymd(2022, 6, 3);
+        assert_eq!(date_to_serial(date), 44715);
+        assert_eq!(cell_to_date("44715")?, date);
+        // the time of the day is ignored
+        assert_eq!(cell_to_date("44715.75")?, date);
+        assert_eq!(cell_to_date("03.06.2022")?, date);
+        assert_eq!(cell_to_date("03/06/2022")?, date);
+        assert!(cell_to_date("fail").is_err());
+        assert_eq!(date_format("/"), "dd/mm/yyyy");
+        assert_eq!(amount_format("NOK"), "#,##0.00 \"NOK\"");
+        Ok(())
+    }
+
     #[test]
     fn test_remove_duplicates() {
-        let accounting_date = vec![
-            NaiveDate::parse_from_str("01.01.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-        ];
-        let interest_date = vec![
-            NaiveDate::parse_from_str("01.01.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-        ];
-        let archive_reference = vec![String::from("1"), String::from("1"), String::from("1")];
-        let counter_account = vec![
-            String::from("counter"),
-            String::from("counter"),
-            String::from("counter"),
-        ];
-        let types = vec![
-            String::from("type1"),
-            String::from("type2"),
-            String::from("type3"),
-        ];
-        let text = vec![
-            String::from("text1"),
-            String::from("text2"),
-            String::from("text3"),
-        ];
-        let out_of_account = vec![0.0, 1.0, 1.0];
-        let into_account = vec![1.0, 0.0, 0.0];
-        let account = vec![String::from("a"), String::from("a"), String::from("a")];
-
-        let wb1 = workbook::WorkbookInfo::new(
-            accounting_date,
-            interest_date,
-            archive_reference,
-            counter_account,
-            types,
-            text,
-            out_of_account,
-            into_account,
-            account,
-        )
-        .unwrap();
-        let accounting_date = vec![
-            NaiveDate::parse_from_str("01.01.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-        ];
-        let interest_date = vec![
-            NaiveDate::parse_from_str("01.01.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-            NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap(),
-        ];
-        let archive_reference = vec![String::from("1"), String::from("1"), String::from("1")];
-        let counter_account = vec![
-            String::from("counter"),
-            String::from("counter"),
-            String::from("counter"),
-        ];
-        let types = vec![
-            String::from("type1"),
-            String::from("type2"),
-            String::from("type3"),
-        ];
-        let text = vec![
-            String::from("text1"),
-            String::from("text2"),
-            String::from("text3"),
-        ];
-        let out_of_account = vec![0.0, 0.0, 1.0];
-        let into_account = vec![1.0, 1.0, 0.0];
-        let account = vec![String::from("a"), String::from("a"), String::from("a")];
-
-        let wb2 = workbook::WorkbookInfo::new(
-            accounting_date,
-            interest_date,
-            archive_reference,
-            counter_account,
-            types,
-            text,
-            out_of_account,
-            into_account,
-            account,
-        )
-        .unwrap();
+        let date = NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap();
+        let transaction = |types: &str, amount: i64| {
+            let mut transaction = Transaction::new(date, Money::from_kroner(amount));
+            transaction.archive_reference = String::from("1");
+            transaction.counter_account = String::from("counter");
+            transaction.types = String::from(types);
+            transaction.text = String::from("text");
+            transaction.account = String::from("a");
+            transaction
+        };
+
+        let wb1 = Transactions::from(vec![
+            transaction("type1", 1),
+            transaction("type2", -1),
+            transaction("type3", -1),
+        ]);
+        let wb2 = Transactions::from(vec![
+            transaction("type1", 1),
+            transaction("type2", 1),
+            transaction("type3", -1),
+        ]);
 
         let wb = remove_duplicates(wb1, wb2);
-        let res = workbook::WorkbookInfo::new(
-            vec![NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap()],
-            vec![NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap()],
-            vec![String::from("1")],
-            vec![String::from("counter")],
-            vec![String::from("type2")],
-            vec![String::from("text2")],
-            vec![1.0],
-            vec![0.0],
-            vec![String::from("a")],
-        )
-        .unwrap();
-        assert_eq!(wb, res);
+        assert_eq!(wb, Transactions::from(vec![transaction("type2", -1)]));
+    }
+
+    #[test]
+    fn test_text_similarity() {
+        assert_eq!(text_similarity("KIWI 123", "kiwi  123"), 1.0);
+        assert_eq!(
+            text_similarity("KIWI 123", "*4321 05.06 KIWI 123 OSLO"),
+            1.0
+        );
+        assert!(text_similarity("Rema 1000 Grunerlokka", "Rema 1000 Grunerlokk") > 0.9);
+        assert!(text_similarity("KIWI 123", "Spotify") < TEXT_SIMILARITY);
+        assert_eq!(text_similarity("", "KIWI"), 0.0);
+    }
+
+    #[test]
+    fn test_find_suspects() {
+        let date = |day: u32| NaiveDate::from_ymd(2022, 6, day);
+        let transaction = |day: u32, text: &str, amount: i64| {
+            let mut transaction = Transaction::new(date(day), Money::from_kroner(amount));
+            transaction.text = String::from(text);
+            transaction.account = String::from("a");
+            transaction
+        };
+        let workbook = Transactions::from(vec![
+            transaction(1, "Reservert KIWI 123", -45),
+            transaction(1, "Spotify", -99),
+        ]);
+        let new = Transactions::from(vec![
+            // booked version of the pending transaction
+            transaction(3, "KIWI 123 OSLO", -45),
+            // too many days apart
+            transaction(9, "Spotify", -99),
+            // another amount
+            transaction(2, "KIWI 123", -46),
+        ]);
+        let suspects = find_suspects(&new, &workbook, 3);
+        assert_eq!(suspects.len(), 1);
+        assert_eq!(suspects[0].index, 0);
+        assert_eq!(suspects[0].existing, workbook[0]);
+        assert_eq!(find_suspects(&new, &workbook, 10).len(), 2);
+    }
+
+    #[test]
+    fn test_find_suspects_keeps_repeats() {
+        let date = NaiveDate::from_ymd(2022, 6, 1);
+        let coffee = || {
+            let mut transaction = Transaction::new(date, Money::from_kroner(-45));
+            transaction.text = String::from("Coffee");
+            transaction.account = String::from("a");
+            transaction
+        };
+        // two coffees on the same day in the export, one of them is already in the workbook
+        let export = Transactions::from(vec![coffee(), coffee()]);
+        let workbook = Transactions::from(vec![coffee()]);
+        let (new, duplicates) = split_duplicates(export, workbook.clone());
+        assert_eq!(new.len(), 1);
+        assert_eq!(duplicates.len(), 1);
+        assert!(find_suspects(&new, &workbook, 3).is_empty());
+
+        // a similar text is still a suspect
+        let mut reserved = coffee();
+        reserved.text = String::from("Reservert Coffee");
+        let workbook = Transactions::from(vec![reserved]);
+        assert_eq!(find_suspects(&new, &workbook, 3).len(), 1);
+    }
+
+    #[test]
+    fn test_remove_duplicates_keeps_repeats() {
+        let date = NaiveDate::parse_from_str("01.02.2022", "%d.%m.%Y").unwrap();
+        let coffee = || {
+            let mut transaction = Transaction::new(date, Money::from_kroner(-45));
+            transaction.text = String::from("Coffee");
+            transaction
+        };
+        let mut workbook_coffee = coffee();
+        workbook_coffee.text = String::from("COFFEE ");
+
+        // two coffees on the same day, one of them is already in the workbook
+        let wb1 = Transactions::from(vec![coffee(), coffee()]);
+        let wb2 = Transactions::from(vec![workbook_coffee]);
+        assert_eq!(remove_duplicates(wb1.clone(), wb2.clone()).len(), 1);
+        assert_eq!(remove_duplicates(wb1.clone(), Transactions::new()).len(), 2);
+        assert!(remove_duplicates(wb1.slice(0..1), wb2.clone()).is_empty());
+
+        let (new, duplicates) = split_duplicates(wb1.clone(), wb2);
+        assert_eq!(new.len(), 1);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0], wb1[0]);
     }
 }
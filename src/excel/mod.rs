@@ -1,5 +1,10 @@
+pub mod classifier;
+pub mod conflicts;
+pub mod lib;
 pub mod reading;
+pub mod rules;
+pub mod schema;
+pub mod template;
+pub mod test_setup;
+pub mod validate;
 pub mod writing;
-pub mod lib;
-pub mod workbook;
-pub mod test_setup;
\ No newline at end of file
@@ -1,36 +1,67 @@
 use crate::accounting;
 
 use super::lib;
-use super::workbook;
+use super::rules::Rule;
+use super::schema::{cell, Schema};
+use crate::accounting::money::Money;
+use crate::accounting::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
 use crate::file;
 
+use std::collections::HashMap;
 use std::error;
 
 #[derive(Default, Debug)]
 pub struct Categories {
     pub from_text: Vec<Vec<String>>,
     pub from_type: Vec<Vec<String>>,
+    // the type categories and then the key words of from_text, in the same order
+    pub sheet_rules: Vec<Rule>,
+    // the rules of the rules file, they are tried before the categories sheet
+    pub rules: Vec<Rule>,
 }
 
 impl Categories {
     pub fn new(from_text: Vec<Vec<String>>, from_type: Vec<Vec<String>>) -> Self {
         // checks that all categories contain at least two elements
-        let from_text = from_text.into_iter().filter(|vec| vec.len() >= 2).collect();
-        let from_type = from_type.into_iter().filter(|vec| vec.len() >= 2).collect();
+        let from_text: Vec<Vec<String>> =
+            from_text.into_iter().filter(|vec| vec.len() >= 2).collect();
+        let from_type: Vec<Vec<String>> =
+            from_type.into_iter().filter(|vec| vec.len() >= 2).collect();
+
+        let mut sheet_rules = vec![];
+        for cat in &from_type {
+            sheet_rules.push(Rule::from_type(&cat[0], &cat[1]));
+        }
+        for cat in &from_text {
+            for keyword in &cat[2..] {
+                sheet_rules.push(Rule::from_keyword_or_text(&cat[0], &cat[1], keyword));
+            }
+        }
 
         Self {
             from_text: from_text,
             from_type: from_type,
+            sheet_rules,
+            rules: vec![],
         }
     }
+
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    // every rule in the order they are tried, the rules file first
+    pub fn all_rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter().chain(self.sheet_rules.iter())
+    }
 }
 
-pub fn get_categories(path: &str) -> Result<Categories, Box<dyn error::Error>> {
-    let book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name("Kontoutskrift")
-        .map_err(|e| format!("could not open worksheet 'Kontoutskrift': {:?}", e))?;
+pub fn get_categories(path: &str, schema: &Schema) -> Result<Categories, Box<dyn error::Error>> {
+    schema.validate()?;
+    let book = file::lib::open_file(path)?;
+    let sheet = open_sheet(&book, path, &schema.category_sheet)?;
 
     let mut from_text = vec![];
     let mut from_type = vec![];
@@ -47,6 +78,7 @@ pub fn get_categories(path: &str) -> Result<Categories, Box<dyn error::Error>> {
         let cat_type = sheet.get_value_by_column_and_row(&col, &3);
         // if cat_type does not exists (expense, income etc.) continue
         if cat_type == "" {
+            col += 1;
             continue;
         }
 
@@ -85,149 +117,174 @@ pub fn get_categories(path: &str) -> Result<Categories, Box<dyn error::Error>> {
 }
 
 // read from spreadsheet file
-pub fn get_accounts(path: &str) -> Result<Vec<accounting::lib::Account>, Box<dyn error::Error>> {
-    let book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name("Informasjon")
-        .map_err(|e| format!("could not open worksheet 'Informasjon': {:?}", e))?;
+pub fn get_accounts(
+    path: &str,
+    schema: &Schema,
+) -> Result<Vec<accounting::lib::Account>, Box<dyn error::Error>> {
+    schema.validate()?;
+    let book = file::lib::open_file(path)?;
+    let sheet = open_sheet(&book, path, &schema.account_sheet)?;
 
     let mut accounts = vec![];
-    // The account names are in the B file
-    // The account number are in the C file
-    // The first line is the header, start on line 2
-    let mut line = 2;
+    // The currency of the account is NOK if it is empty
+    // start on the line after the header
+    let columns = &schema.account_columns;
+    let mut line = schema.first_row();
     loop {
-        let account_name = sheet.get_value(&(String::from("B") + &line.to_string()));
-        let account_number = match sheet
-            .get_value(&(String::from("C") + &line.to_string()))
-            .parse::<u64>()
-        {
-            Ok(num) => num,
-            // if the account_number is not valid, just break
-            Err(_) => break,
-        };
+        let account_name = sheet.get_value(&cell(&columns.name, line));
         if account_name == "" {
             break;
         }
+        let number_cell = cell(&columns.number, line);
+        let account_number = sheet.get_value(&number_cell);
+        let account_number =
+            account_number
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| Error::BadAccountNumber {
+                    file: String::from(path),
+                    location: Location::cell(&schema.account_sheet, &number_cell),
+                    value: account_number.clone(),
+                })?;
+        let currency = sheet.get_value(&cell(&columns.currency, line));
         accounts.push(accounting::lib::Account {
             name: account_name,
             number: account_number,
+            currency: accounting::currency::normalize(&currency),
         });
         line += 1;
     }
     Ok(accounts)
 }
 
+// the currency of each account by name, empty if the workbook has no account sheet
+fn account_currencies(
+    book: &umya_spreadsheet::Spreadsheet,
+    schema: &Schema,
+) -> HashMap<String, String> {
+    let mut currencies = HashMap::new();
+    let sheet = match book.get_sheet_by_name(&schema.account_sheet) {
+        Ok(sheet) => sheet,
+        Err(_) => return currencies,
+    };
+    let columns = &schema.account_columns;
+    let mut line = schema.first_row();
+    loop {
+        let name = sheet.get_value(&cell(&columns.name, line));
+        if name.is_empty() {
+            break;
+        }
+        let currency = sheet.get_value(&cell(&columns.currency, line));
+        currencies.insert(name, accounting::currency::normalize(&currency));
+        line += 1;
+    }
+    currencies
+}
+
+// a lenient import leaves out the rows it cannot read and returns them, else the first one
+// is the error
 pub fn get_transactions(
     path: &str,
-    bank: accounting::bank::Bank,
-    account: &str,
-) -> Result<workbook::WorkbookInfo, Box<dyn error::Error>> {
-    match bank {
-        accounting::bank::Bank::SBanken => {
-            match accounting::bank::SBanken::get_transactions(path) {
-                // want to reverse the data (plot the oldest data first)
-                Ok(sbanken) => {
-                    let length = sbanken.accounting_date.len();
-                    Ok(workbook::WorkbookInfo::new(
-                        sbanken.accounting_date.into_iter().rev().collect(),
-                        sbanken.interest_date.into_iter().rev().collect(),
-                        sbanken.archive_reference.into_iter().rev().collect(),
-                        sbanken.counter_account.into_iter().rev().collect(),
-                        sbanken.types.into_iter().rev().collect(),
-                        sbanken.text.into_iter().rev().collect(),
-                        sbanken.out_of_account.into_iter().rev().collect(),
-                        sbanken.into_account.into_iter().rev().collect(),
-                        vec![String::from(account); length],
-                    )?)
-                }
-                Err(e) => Err(e),
-            }
+    importer: &dyn accounting::bank::BankImporter,
+    account: &accounting::lib::Account,
+    lenient: bool,
+) -> Result<(Transactions, Vec<accounting::bank::SkippedRow>), Box<dyn error::Error>> {
+    let (mut transactions, skipped) = if lenient {
+        importer.parse_lenient(path)?
+    } else {
+        (importer.parse(path)?, vec![])
+    };
+    transactions.set_account(&account.name);
+    // the export only tells the currency if it differs from the account
+    for transaction in transactions.iter_mut() {
+        if transaction.currency.is_empty() {
+            transaction.currency = accounting::currency::normalize(&account.currency);
+        }
+    }
+    // want the oldest data first, most banks export the newest first, so those are turned
+    // around before they are sorted, the rows of a day keep the order they were booked in
+    if let (Some(first), Some(last)) = (transactions.first(), transactions.last()) {
+        if first.accounting_date > last.accounting_date {
+            transactions.reverse();
         }
     }
+    transactions.sort_by_date();
+    Ok((transactions, skipped))
 }
 
 pub fn get_workbook_transactions(
     path: &str,
-) -> Result<workbook::WorkbookInfo, Box<dyn error::Error>> {
-    let book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name("Informasjon")
-        .map_err(|e| format!("could not open worksheet 'Informasjon': {:?}", e))?;
-
-    let mut accounting_date = vec![];
-    let mut interest_date = vec![];
-    let mut archive_reference = vec![];
-    let mut counter_account = vec![];
-    let mut types = vec![];
-    let mut text = vec![];
-    let mut out_of_account = vec![];
-    let mut into_account = vec![];
-    let mut account = vec![];
-
-    // sbanken transactions start on excel line 4
-    let mut row = 2;
+    schema: &Schema,
+) -> Result<Transactions, Box<dyn error::Error>> {
+    schema.validate()?;
+    let book = file::lib::open_file(path)?;
+    let sheet = open_sheet(&book, path, &schema.transaction_sheet)?;
+    let columns = &schema.columns;
+    // a row without a currency is in the currency of its account
+    let currencies = account_currencies(&book, schema);
+
+    let mut transactions = Transactions::new();
+
+    // dates are excel dates or text in older workbooks, the errors tell which cell is wrong
+    let date = |column: &str, row: u32| {
+        let value = sheet.get_value(&cell(column, row));
+        lib::cell_to_date(&value).map_err(|_| Error::BadDate {
+            file: String::from(path),
+            location: Location::cell(&schema.transaction_sheet, &cell(column, row)),
+            value,
+        })
+    };
+    let amount = |column: &str, row: u32| {
+        let value = sheet.get_value(&cell(column, row));
+        Money::parse(&value).map_err(|_| Error::BadAmount {
+            file: String::from(path),
+            location: Location::cell(&schema.transaction_sheet, &cell(column, row)),
+            value,
+        })
+    };
+
+    let mut row = schema.first_row();
     loop {
-        // accounting date
-        let accounting_date_str =
-            &sheet.get_formatted_value(&(String::from("A") + &row.to_string()));
-        // if accounting_date_str is empty, end of transactions
-        if accounting_date_str == "" {
+        // if the accounting date is empty, end of transactions
+        if sheet.get_value(&cell(&columns.accounting_date, row)) == "" {
             break;
         }
-        let delimiter = lib::get_delimiter(accounting_date_str);
-        let s = lib::string_to_date(accounting_date_str, &delimiter)
-            .map_err(|e| format!("accounting date is not valid: {:?}", e))?;
-        accounting_date.push(s);
-
-        // interest date
-        let interest_date_str = &sheet.get_formatted_value(&(String::from("B") + &row.to_string()));
-        let delimiter = lib::get_delimiter(accounting_date_str);
-        let s = lib::string_to_date(interest_date_str, &delimiter)
-            .map_err(|e| format!("interest date is not valid: {:?}", e))?;
-        interest_date.push(s);
-
-        archive_reference.push(sheet.get_value(&(String::from("C") + &row.to_string())));
-        counter_account.push(sheet.get_value(&(String::from("D") + &row.to_string())));
-        types.push(sheet.get_value(&(String::from("E") + &row.to_string())));
-        text.push(sheet.get_value(&(String::from("F") + &row.to_string())));
-
-        let out_of_account_str = sheet.get_value(&(String::from("G") + &row.to_string()));
-        let f = out_of_account_str
-            .parse::<f64>()
-            .map_err(|e| format!("could not parse out of account string to f64: {:?}", e))?;
-        out_of_account.push(f);
-
-        let into_account_str = sheet.get_value(&(String::from("H") + &row.to_string()));
-        let f = into_account_str.parse::<f64>().map_err(|e| format!("could not parse into account string to f64: {:?}", e))?;
-        into_account.push(f);
-
-        let account_str = sheet.get_value(&(String::from("J") + &row.to_string()));
-        account.push(account_str);
+        let accounting_date = date(&columns.accounting_date, row)?;
+        let interest_date = date(&columns.interest_date, row)?;
+        let out_of_account = amount(&columns.out_of_account, row)?;
+        let into_account = amount(&columns.into_account, row)?;
+        let account = sheet.get_value(&cell(&columns.account, row));
+        let mut currency = sheet.get_value(&cell(&columns.currency, row));
+        if currency.trim().is_empty() {
+            currency = currencies.get(&account).cloned().unwrap_or_default();
+        }
+
+        transactions.push(Transaction {
+            accounting_date,
+            interest_date,
+            archive_reference: sheet.get_value(&cell(&columns.archive_reference, row)),
+            counter_account: sheet.get_value(&cell(&columns.counter_account, row)),
+            types: sheet.get_value(&cell(&columns.types, row)),
+            text: sheet.get_value(&cell(&columns.text, row)),
+            out_of_account,
+            into_account,
+            account,
+            currency,
+            category: sheet.get_value(&cell(&columns.group, row)),
+            ..Transaction::new(accounting_date, Money::ZERO)
+        });
 
         row += 1;
     }
 
-    let workbook = workbook::WorkbookInfo::new(
-        accounting_date,
-        interest_date,
-        archive_reference,
-        counter_account,
-        types,
-        text,
-        out_of_account,
-        into_account,
-        account)?;
-    Ok(workbook)
+    Ok(transactions)
 }
 
-pub fn get_first_empty_line(sheet: &mut umya_spreadsheet::Worksheet) -> usize {
-    // checks the A file, assumes the rest of the slots also are empty if A row is empty
-    let mut row = 1;
+pub fn get_first_empty_line(sheet: &umya_spreadsheet::Worksheet, schema: &Schema) -> u32 {
+    // checks the accounting date column, assumes the rest of the slots also are empty if it is empty
+    let mut row = schema.first_row();
     loop {
-        let value = sheet.get_value(&(String::from("A") + &row.to_string()));
+        let value = sheet.get_value(&cell(&schema.columns.accounting_date, row));
 
         // if value is empty (row is empty)
         if value == "" {
@@ -238,6 +295,32 @@ pub fn get_first_empty_line(sheet: &mut umya_spreadsheet::Worksheet) -> usize {
     row
 }
 
+// the sheet with the name, the error tells which sheet is missing in which file
+pub fn open_sheet<'a>(
+    book: &'a umya_spreadsheet::Spreadsheet,
+    path: &str,
+    name: &str,
+) -> Result<&'a umya_spreadsheet::Worksheet, Error> {
+    book.get_sheet_by_name(name)
+        .map_err(|_| missing_sheet(path, name))
+}
+
+pub fn open_sheet_mut<'a>(
+    book: &'a mut umya_spreadsheet::Spreadsheet,
+    path: &str,
+    name: &str,
+) -> Result<&'a mut umya_spreadsheet::Worksheet, Error> {
+    book.get_sheet_by_name_mut(name)
+        .map_err(|_| missing_sheet(path, name))
+}
+
+fn missing_sheet(path: &str, name: &str) -> Error {
+    Error::MissingSheet {
+        file: String::from(path),
+        sheet: String::from(name),
+    }
+}
+
 #[cfg(test)]
 mod tests_excel_reading {
     use super::*;
@@ -254,6 +337,133 @@ mod tests_excel_reading {
 
     #[test]
     fn test_get_accounts() {
-        assert_eq!(get_accounts("test").is_err(), true)
+        assert_eq!(get_accounts("test", &Schema::default()).is_err(), true)
+    }
+
+    #[test]
+    fn test_get_categories() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_categories.xlsx";
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.new_sheet("Kategorier")?;
+        sheet.get_cell_mut("A1").set_value("from text");
+        sheet.get_cell_mut("A2").set_value("Food");
+        sheet.get_cell_mut("A3").set_value("Expense");
+        sheet.get_cell_mut("A4").set_value("kiwi");
+        // a column without income/expense is skipped
+        sheet.get_cell_mut("B2").set_value("Unused");
+        sheet.get_cell_mut("C1").set_value("from type");
+        sheet.get_cell_mut("C2").set_value("Salary");
+        sheet.get_cell_mut("C3").set_value("Income");
+        file::lib::save_file(&book, path)?;
+
+        let categories = get_categories(path, &Schema::default());
+        std::fs::remove_file(path)?;
+        let categories = categories?;
+        assert_eq!(categories.from_text, vec![vec!["Food", "Expense", "kiwi"]]);
+        assert_eq!(categories.from_type, vec![vec!["Salary", "Income"]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reading_errors() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_reading_errors.xlsx";
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.new_sheet("Kontoutskrift")?;
+        sheet.get_cell_mut("A2").set_value("03.06.2022");
+        sheet.get_cell_mut("B2").set_value("03.06.2022");
+        sheet.get_cell_mut("G2").set_value("12,50");
+        file::lib::save_file(&book, path)?;
+
+        let transactions = get_workbook_transactions(path, &Schema::default());
+        let categories = get_categories(path, &Schema::default());
+        std::fs::remove_file(path)?;
+        assert_eq!(
+            *transactions.unwrap_err().downcast::<Error>()?,
+            Error::BadAmount {
+                file: String::from(path),
+                location: Location::cell("Kontoutskrift", "G2"),
+                value: String::from("12,50"),
+            }
+        );
+        assert_eq!(
+            *categories.unwrap_err().downcast::<Error>()?,
+            Error::MissingSheet {
+                file: String::from(path),
+                sheet: String::from("Kategorier"),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_transactions_oldest_first() -> Result<(), Box<dyn error::Error>> {
+        let account = accounting::lib::Account {
+            name: String::from("Main"),
+            ..Default::default()
+        };
+        let header = "Bokføringsdato;Rentedato;Tekst;Beløp;Saldo\n";
+        let mut texts = vec![];
+        // the day of the month and the text of each row
+        for (i, rows) in [
+            // newest first, b was booked after a
+            [(3, "c"), (2, "b"), (2, "a"), (1, "x")],
+            // oldest first
+            [(1, "x"), (2, "a"), (2, "b"), (3, "c")],
+            // not in order
+            [(2, "a"), (3, "c"), (1, "x"), (2, "b")],
+            // one day, read in the order of the file
+            [(2, "a"), (2, "b"), (2, "c"), (2, "x")],
+        ]
+        .iter()
+        .enumerate()
+        {
+            let path = format!("test_oldest_first_{}.csv", i);
+            let content: String = rows
+                .iter()
+                .map(|(day, text)| format!("2022-06-0{0};2022-06-0{0};{1};-1,00;0,00\n", day, text))
+                .collect();
+            std::fs::write(&path, String::from(header) + &content)?;
+            let transactions =
+                get_transactions(&path, &accounting::bank::Handelsbanken, &account, false);
+            std::fs::remove_file(&path)?;
+            texts.push(transactions?.0.column(|t| t.text.clone()).join(""));
+        }
+        assert_eq!(texts, vec!["xabc", "xabc", "xabc", "abcx"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_workbook_currency_from_account() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_workbook_currency_from_account.xlsx";
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book.new_sheet("Kontoutskrift")?;
+        for (row, account, currency) in [(2, "Euro", ""), (3, "Euro", "NOK"), (4, "Brukskonto", "")]
+        {
+            for column in ["A", "B"] {
+                sheet
+                    .get_cell_mut(&format!("{}{}", column, row))
+                    .set_value("03.06.2022");
+            }
+            sheet.get_cell_mut(&format!("G{}", row)).set_value("12.5");
+            sheet.get_cell_mut(&format!("H{}", row)).set_value("0");
+            sheet.get_cell_mut(&format!("J{}", row)).set_value(account);
+            sheet.get_cell_mut(&format!("P{}", row)).set_value(currency);
+        }
+        let sheet = book.new_sheet("Informasjon")?;
+        sheet.get_cell_mut("B2").set_value("Euro");
+        sheet.get_cell_mut("C2").set_value("12345678903");
+        sheet.get_cell_mut("D2").set_value("eur");
+        sheet.get_cell_mut("B3").set_value("Brukskonto");
+        sheet.get_cell_mut("C3").set_value("12345678904");
+        file::lib::save_file(&book, path)?;
+
+        let transactions = get_workbook_transactions(path, &Schema::default());
+        std::fs::remove_file(path)?;
+        // an empty currency cell is the currency of the account, not NOK
+        assert_eq!(
+            transactions?.column(|t| t.currency.clone()),
+            ["EUR", "NOK", "NOK"]
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,397 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::accounting::money::Money;
+use crate::accounting::transaction::Transaction;
+
+// the file the GUI reads the rules from, there are no rules if it does not exist
+pub const RULES_FILE: &str = "rules.toml";
+
+// a key word in the categories sheet that starts with this is a regular expression
+pub const REGEX_PREFIX: &str = "re:";
+
+// How a rule matches a text, both ignore the case
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // the old key words, e.g. "kiwi" also matches "KIWIMARKET"
+    Contains(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn regex(pattern: &str) -> Result<Self, Box<dyn error::Error>> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("'{}' is not a valid regular expression: {}", pattern, e))?;
+        Ok(Pattern::Regex(regex))
+    }
+
+    // a regular expression if it starts with "re:", else a part of the text
+    pub fn from_keyword(keyword: &str) -> Result<Self, Box<dyn error::Error>> {
+        match keyword.strip_prefix(REGEX_PREFIX) {
+            Some(pattern) => Pattern::regex(pattern.trim()),
+            None => Ok(Pattern::Contains(keyword.to_lowercase())),
+        }
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            Pattern::Contains(keyword) => value.to_lowercase().contains(keyword),
+            Pattern::Regex(regex) => regex.is_match(value),
+        }
+    }
+
+    // true if every text the other pattern matches is matched by this one, regular
+    // expressions are only compared as text
+    pub fn covers(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            (Pattern::Contains(keyword), Pattern::Contains(other)) => {
+                other.contains(keyword.as_str())
+            }
+            (Pattern::Regex(regex), Pattern::Regex(other)) => regex.as_str() == other.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Contains(keyword) => write!(f, "\"{}\"", keyword),
+            Pattern::Regex(regex) => write!(f, "/{}/", regex.as_str()),
+        }
+    }
+}
+
+// Money into the account is in, money out of the account is out
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    In,
+    Out,
+}
+
+// A rule as it is written in the rules file, text and types are regular expressions and the
+// amounts are in kroner without the sign
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RuleSpec {
+    pub group: String,
+    pub income_expense: String,
+    // the rule with the highest priority wins, the default is 0
+    pub priority: i32,
+    pub text: Option<String>,
+    pub types: Option<String>,
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub direction: Option<Direction>,
+    pub account: Option<String>,
+    pub counter_account: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct RulesFile {
+    rules: Vec<RuleSpec>,
+}
+
+// Gives the group and income/expense to the transactions that meet every condition it has
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub group: String,
+    pub income_expense: String,
+    // the matching rule with the highest priority wins, on a tie the one that is tried first
+    pub priority: i32,
+    pub text: Option<Pattern>,
+    pub types: Option<Pattern>,
+    // the amount without the sign, both ends are included
+    pub min_amount: Option<Money>,
+    pub max_amount: Option<Money>,
+    pub direction: Option<Direction>,
+    pub account: Option<String>,
+    pub counter_account: Option<String>,
+}
+
+impl Rule {
+    fn new(group: &str, income_expense: &str) -> Self {
+        Self {
+            group: String::from(group),
+            income_expense: String::from(income_expense),
+            priority: 0,
+            text: None,
+            types: None,
+            min_amount: None,
+            max_amount: None,
+            direction: None,
+            account: None,
+            counter_account: None,
+        }
+    }
+
+    pub fn from_spec(spec: &RuleSpec) -> Result<Self, Box<dyn error::Error>> {
+        if spec.group.trim().is_empty() {
+            return Err("a rule needs a group".into());
+        }
+        let mut rule = Rule::new(&spec.group, &spec.income_expense);
+        rule.priority = spec.priority;
+        rule.text = spec.text.as_deref().map(Pattern::regex).transpose()?;
+        rule.types = spec.types.as_deref().map(Pattern::regex).transpose()?;
+        rule.min_amount = spec.min_amount.map(Money::from_f64);
+        rule.max_amount = spec.max_amount.map(Money::from_f64);
+        rule.direction = spec.direction;
+        rule.account = spec.account.clone();
+        rule.counter_account = spec.counter_account.clone();
+        Ok(rule)
+    }
+
+    // a key word of the categories sheet, the text to look for and then the conditions,
+    // e.g. "vipps; <200; out" or "re:^kiwi\b; account=Main"
+    pub fn from_keyword(
+        group: &str,
+        income_expense: &str,
+        keyword: &str,
+    ) -> Result<Self, Box<dyn error::Error>> {
+        let mut parts = keyword.split(';').map(|part| part.trim());
+        let mut rule = Rule::new(group, income_expense);
+        rule.text = Some(Pattern::from_keyword(parts.next().unwrap_or_default())?);
+        for condition in parts.filter(|part| !part.is_empty()) {
+            let lower = condition.to_lowercase();
+            if lower == "in" {
+                rule.direction = Some(Direction::In);
+            } else if lower == "out" {
+                rule.direction = Some(Direction::Out);
+            } else if let Some(account) = lower.strip_prefix("account=") {
+                rule.account = Some(String::from(account.trim()));
+            } else if let Some(counter_account) = lower.strip_prefix("counter=") {
+                rule.counter_account = Some(String::from(counter_account.trim()));
+            } else if let Some(priority) = lower.strip_prefix("priority=") {
+                rule.priority = priority.trim().parse().map_err(|_| {
+                    format!("the priority in '{}' is not a whole number", condition)
+                })?;
+            } else if let Some(amount) = lower.strip_prefix("<=") {
+                rule.max_amount = Some(parse_amount(amount, condition)?);
+            } else if let Some(amount) = lower.strip_prefix(">=") {
+                rule.min_amount = Some(parse_amount(amount, condition)?);
+            } else if let Some(amount) = lower.strip_prefix('<') {
+                rule.max_amount = Some(parse_amount(amount, condition)? - Money::from_ore(1));
+            } else if let Some(amount) = lower.strip_prefix('>') {
+                rule.min_amount = Some(parse_amount(amount, condition)? + Money::from_ore(1));
+            } else {
+                return Err(format!(
+                    "'{}' in '{}' is not a condition, use e.g. <200, >=50, in, out, account=name, counter=number or priority=1",
+                    condition, keyword
+                )
+                .into());
+            }
+        }
+        Ok(rule)
+    }
+
+    // a key word that is not a valid rule is looked for as it is, like before there were
+    // rules, the workbook check tells which key words they are
+    pub fn from_keyword_or_text(group: &str, income_expense: &str, keyword: &str) -> Self {
+        Rule::from_keyword(group, income_expense, keyword).unwrap_or_else(|_| {
+            let mut rule = Rule::new(group, income_expense);
+            rule.text = Some(Pattern::Contains(keyword.to_lowercase()));
+            rule
+        })
+    }
+
+    // a type category, the type of the transaction contains the name of the group
+    pub fn from_type(group: &str, income_expense: &str) -> Self {
+        let mut rule = Rule::new(group, income_expense);
+        rule.types = Some(Pattern::Contains(group.to_lowercase()));
+        rule
+    }
+
+    // true if this rule matches every transaction the other one matches
+    pub fn covers(&self, other: &Rule) -> bool {
+        let pattern = |this: &Option<Pattern>, other: &Option<Pattern>| match (this, other) {
+            (None, _) => true,
+            (Some(this), Some(other)) => this.covers(other),
+            (Some(_), None) => false,
+        };
+        let equal = |this: &Option<String>, other: &Option<String>| {
+            this.is_none()
+                || this.as_deref().map(str::to_lowercase) == other.as_deref().map(str::to_lowercase)
+        };
+        pattern(&self.text, &other.text)
+            && pattern(&self.types, &other.types)
+            && self
+                .min_amount
+                .is_none_or(|min| other.min_amount.is_some_and(|m| m >= min))
+            && self
+                .max_amount
+                .is_none_or(|max| other.max_amount.is_some_and(|m| m <= max))
+            && (self.direction.is_none() || self.direction == other.direction)
+            && equal(&self.account, &other.account)
+            && equal(&self.counter_account, &other.counter_account)
+    }
+
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let amount = transaction.amount();
+        let direction = if amount.is_negative() {
+            Direction::Out
+        } else {
+            Direction::In
+        };
+        self.text
+            .as_ref()
+            .is_none_or(|text| text.is_match(&transaction.text))
+            && self
+                .types
+                .as_ref()
+                .is_none_or(|types| types.is_match(&transaction.types))
+            && self.min_amount.is_none_or(|min| amount.abs() >= min)
+            && self.max_amount.is_none_or(|max| amount.abs() <= max)
+            && self.direction.is_none_or(|d| d == direction)
+            && self.account.as_ref().is_none_or(|account| {
+                account.to_lowercase() == transaction.account.trim().to_lowercase()
+            })
+            && self.counter_account.as_ref().is_none_or(|counter_account| {
+                counter_account.to_lowercase() == transaction.counter_account.trim().to_lowercase()
+            })
+    }
+}
+
+// e.g. "Dining (Expense): text /^vipps/, out, <= 200.00"
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut conditions = vec![];
+        if let Some(text) = &self.text {
+            conditions.push(format!("text {}", text));
+        }
+        if let Some(types) = &self.types {
+            conditions.push(format!("type {}", types));
+        }
+        if let Some(direction) = self.direction {
+            conditions.push(String::from(match direction {
+                Direction::In => "in",
+                Direction::Out => "out",
+            }));
+        }
+        if let Some(min) = self.min_amount {
+            conditions.push(format!(">= {}", min));
+        }
+        if let Some(max) = self.max_amount {
+            conditions.push(format!("<= {}", max));
+        }
+        if let Some(account) = &self.account {
+            conditions.push(format!("account {}", account));
+        }
+        if let Some(counter_account) = &self.counter_account {
+            conditions.push(format!("counter account {}", counter_account));
+        }
+        if self.priority != 0 {
+            conditions.push(format!("priority {}", self.priority));
+        }
+        write!(
+            f,
+            "{} ({}): {}",
+            self.group,
+            self.income_expense,
+            conditions.join(", ")
+        )
+    }
+}
+
+fn parse_amount(amount: &str, condition: &str) -> Result<Money, Box<dyn error::Error>> {
+    Money::parse(&amount.trim().replace(',', "."))
+        .map_err(|_| format!("the amount in '{}' is not a number", condition).into())
+}
+
+pub fn rules_from_toml(content: &str) -> Result<Vec<Rule>, Box<dyn error::Error>> {
+    let file: RulesFile =
+        toml::from_str(content).map_err(|e| format!("could not read rules: {}", e))?;
+    file.rules.iter().map(Rule::from_spec).collect()
+}
+
+// the rules of the file, none if it does not exist
+pub fn load_rules(path: &str) -> Result<Vec<Rule>, Box<dyn error::Error>> {
+    if !Path::new(path).is_file() {
+        return Ok(vec![]);
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("could not open rules: {:?}", e))?;
+    rules_from_toml(&content).map_err(|e| format!("rules '{}' are not valid: {}", path, e).into())
+}
+
+#[cfg(test)]
+mod tests_rules {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn transaction(text: &str, amount: i64) -> Transaction {
+        Transaction {
+            text: String::from(text),
+            account: String::from("Main"),
+            counter_account: String::from("1234"),
+            ..Transaction::new(NaiveDate::from_ymd(2022, 6, 3), Money::from_kroner(amount))
+        }
+    }
+
+    #[test]
+    fn test_from_keyword() -> Result<(), Box<dyn error::Error>> {
+        let kiwi = Rule::from_keyword("Food", "Expense", "re:^kiwi\\b")?;
+        assert!(kiwi.matches(&transaction("KIWI 123 OSLO", -50)));
+        assert!(!kiwi.matches(&transaction("KIWIMARKET", -50)));
+        // the old key words still match a part of the text
+        let kiwi = Rule::from_keyword("Food", "Expense", "kiwi")?;
+        assert!(kiwi.matches(&transaction("KIWIMARKET", -50)));
+
+        let dining = Rule::from_keyword("Dining", "Expense", "vipps; <200; out")?;
+        assert!(dining.matches(&transaction("Vipps Ola", -199)));
+        assert!(!dining.matches(&transaction("Vipps Ola", -200)));
+        assert!(!dining.matches(&transaction("Vipps Ola", 150)));
+        assert_eq!(
+            dining.to_string(),
+            "Dining (Expense): text \"vipps\", out, <= 199.99"
+        );
+
+        let main = Rule::from_keyword("Other", "Expense", "; >=100; account=main; counter=1234")?;
+        assert!(main.matches(&transaction("anything", 100)));
+        assert!(!main.matches(&transaction("anything", 99)));
+        // the account names are compared without case, also the letters outside ascii
+        let salary = Rule::from_keyword("Salary", "Income", "; account=Lønnskonto")?;
+        assert!(salary.matches(&Transaction {
+            account: String::from("LØNNSKONTO"),
+            ..transaction("anything", 100)
+        }));
+
+        assert!(Rule::from_keyword("Food", "Expense", "kiwi; cheap").is_err());
+        assert!(Rule::from_keyword("Food", "Expense", "re:(kiwi").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rules_from_toml() -> Result<(), Box<dyn error::Error>> {
+        let rules = rules_from_toml(
+            r#"
+            [[rules]]
+            group = "Dining"
+            income_expense = "Expense"
+            text = "^vipps"
+            max_amount = 200.0
+            direction = "out"
+
+            [[rules]]
+            group = "Salary"
+            income_expense = "Income"
+            counter_account = "1234"
+            direction = "in"
+            "#,
+        )?;
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].matches(&transaction("VIPPS Kari", -120)));
+        assert!(!rules[0].matches(&transaction("VIPPS Kari", -220)));
+        assert!(rules[1].matches(&transaction("Lønn", 30000)));
+
+        assert!(rules_from_toml("[[rules]]\ntext = \"x\"").is_err());
+        assert!(load_rules("test_missing_rules.toml")?.is_empty());
+        Ok(())
+    }
+}
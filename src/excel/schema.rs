@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fs;
+use std::path::Path;
+
+// the file the GUI reads the schema from, the default schema is used if it does not exist
+pub const SCHEMA_FILE: &str = "schema.toml";
+
+// Which column (e.g. "A") holds each field of a row in the transaction sheet
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TransactionColumns {
+    pub accounting_date: String,
+    pub interest_date: String,
+    pub archive_reference: String,
+    pub counter_account: String,
+    pub types: String,
+    pub text: String,
+    pub out_of_account: String,
+    pub into_account: String,
+    // the amount in the reporting currency
+    pub amount: String,
+    pub account: String,
+    pub group: String,
+    pub income_expense: String,
+    pub year: String,
+    pub month: String,
+    // a note here keeps re group from changing the row
+    pub note: String,
+    pub currency: String,
+    // the amount paid in another currency, shown in that currency
+    pub original_amount: String,
+}
+
+impl Default for TransactionColumns {
+    fn default() -> Self {
+        Self {
+            accounting_date: String::from("A"),
+            interest_date: String::from("B"),
+            archive_reference: String::from("C"),
+            counter_account: String::from("D"),
+            types: String::from("E"),
+            text: String::from("F"),
+            out_of_account: String::from("G"),
+            into_account: String::from("H"),
+            amount: String::from("I"),
+            account: String::from("J"),
+            group: String::from("K"),
+            income_expense: String::from("L"),
+            year: String::from("M"),
+            month: String::from("N"),
+            note: String::from("O"),
+            currency: String::from("P"),
+            original_amount: String::from("Q"),
+        }
+    }
+}
+
+impl TransactionColumns {
+    // the name and column of every field, in the order they are shown
+    pub fn fields(&self) -> Vec<(&'static str, &String)> {
+        vec![
+            ("accounting date", &self.accounting_date),
+            ("interest date", &self.interest_date),
+            ("archive reference", &self.archive_reference),
+            ("counter account", &self.counter_account),
+            ("type", &self.types),
+            ("text", &self.text),
+            ("out of account", &self.out_of_account),
+            ("into account", &self.into_account),
+            ("amount", &self.amount),
+            ("account", &self.account),
+            ("group", &self.group),
+            ("income/expense", &self.income_expense),
+            ("year", &self.year),
+            ("month", &self.month),
+            ("note", &self.note),
+            ("currency", &self.currency),
+            ("original amount", &self.original_amount),
+        ]
+    }
+
+    pub fn fields_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        vec![
+            ("accounting date", &mut self.accounting_date),
+            ("interest date", &mut self.interest_date),
+            ("archive reference", &mut self.archive_reference),
+            ("counter account", &mut self.counter_account),
+            ("type", &mut self.types),
+            ("text", &mut self.text),
+            ("out of account", &mut self.out_of_account),
+            ("into account", &mut self.into_account),
+            ("amount", &mut self.amount),
+            ("account", &mut self.account),
+            ("group", &mut self.group),
+            ("income/expense", &mut self.income_expense),
+            ("year", &mut self.year),
+            ("month", &mut self.month),
+            ("note", &mut self.note),
+            ("currency", &mut self.currency),
+            ("original amount", &mut self.original_amount),
+        ]
+    }
+}
+
+// Which column holds each field of a row in the account sheet
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AccountColumns {
+    pub name: String,
+    pub number: String,
+    // NOK if it is empty
+    pub currency: String,
+}
+
+impl Default for AccountColumns {
+    fn default() -> Self {
+        Self {
+            name: String::from("B"),
+            number: String::from("C"),
+            currency: String::from("D"),
+        }
+    }
+}
+
+impl AccountColumns {
+    pub fn fields(&self) -> Vec<(&'static str, &String)> {
+        vec![
+            ("name", &self.name),
+            ("number", &self.number),
+            ("currency", &self.currency),
+        ]
+    }
+
+    pub fn fields_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        vec![
+            ("name", &mut self.name),
+            ("number", &mut self.number),
+            ("currency", &mut self.currency),
+        ]
+    }
+}
+
+// Describes the layout of the workbook, the sheet names and the column of each field
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Schema {
+    pub transaction_sheet: String,
+    pub category_sheet: String,
+    pub account_sheet: String,
+    // the row (1-indexed) with the headers of the transaction and account sheets,
+    // the rows start on the next one
+    pub header_row: u32,
+    pub columns: TransactionColumns,
+    pub account_columns: AccountColumns,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self {
+            transaction_sheet: String::from("Kontoutskrift"),
+            category_sheet: String::from("Kategorier"),
+            account_sheet: String::from("Informasjon"),
+            header_row: 1,
+            columns: TransactionColumns::default(),
+            account_columns: AccountColumns::default(),
+        }
+    }
+}
+
+impl Schema {
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn error::Error>> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("could not open schema: {:?}", e))?;
+        Schema::from_toml(&content)
+            .map_err(|e| format!("schema '{}' is not valid: {}", path, e).into())
+    }
+
+    // the default schema if the file does not exist
+    pub fn load(path: &str) -> Result<Self, Box<dyn error::Error>> {
+        if !Path::new(path).is_file() {
+            return Ok(Schema::default());
+        }
+        Schema::from_file(path)
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, Box<dyn error::Error>> {
+        let schema: Schema =
+            toml::from_str(content).map_err(|e| format!("could not read schema: {}", e))?;
+        schema.validate()?;
+        Ok(schema)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn error::Error>> {
+        self.validate()?;
+        let content =
+            toml::to_string(self).map_err(|e| format!("could not write schema: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("could not save schema: {:?}", e))?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn error::Error>> {
+        for (name, sheet) in [
+            ("transaction", &self.transaction_sheet),
+            ("category", &self.category_sheet),
+            ("account", &self.account_sheet),
+        ] {
+            if sheet.trim().is_empty() {
+                return Err(format!("the schema needs a {} sheet", name).into());
+            }
+        }
+        if self.header_row == 0 {
+            return Err("the header row starts on 1".into());
+        }
+        // the transaction and account sheets are checked on their own, they may share a column
+        for fields in [self.columns.fields(), self.account_columns.fields()] {
+            let mut seen: Vec<&String> = vec![];
+            for (name, column) in fields {
+                valid_column(name, column)?;
+                if seen.contains(&column) {
+                    return Err(format!("column {} is used for more than one field", column).into());
+                }
+                seen.push(column);
+            }
+        }
+        Ok(())
+    }
+
+    // the first row after the header
+    pub fn first_row(&self) -> u32 {
+        self.header_row + 1
+    }
+}
+
+// the coordinate of a cell, e.g. cell("A", 2) is "A2"
+pub fn cell(column: &str, row: impl ToString) -> String {
+    String::from(column) + &row.to_string()
+}
+
+fn valid_column(name: &str, column: &str) -> Result<(), Box<dyn error::Error>> {
+    if column.is_empty() || !column.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!("'{}' is not a valid column for {}", column, name).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_schema {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() -> Result<(), Box<dyn error::Error>> {
+        let schema = Schema::from_toml(
+            "transaction_sheet = \"Transactions\"\n\
+             header_row = 3\n\
+             [columns]\n\
+             text = \"R\"\n",
+        )?;
+        assert_eq!(schema.transaction_sheet, "Transactions");
+        assert_eq!(schema.account_sheet, "Informasjon");
+        assert_eq!(schema.first_row(), 4);
+        assert_eq!(schema.columns.text, "R");
+        assert_eq!(schema.columns.types, "E");
+        assert_eq!(Schema::from_toml(&toml::to_string(&schema)?)?, schema);
+
+        assert!(Schema::from_toml("header_row = 0").is_err());
+        assert!(Schema::from_toml("[columns]\ntext = \"a\"").is_err());
+        // text and type in the same column
+        assert!(Schema::from_toml("[columns]\ntext = \"E\"").is_err());
+        // account name and number in the same column, the account sheet has its own columns
+        assert!(Schema::from_toml("[account_columns]\nnumber = \"B\"").is_err());
+        assert!(Schema::from_toml("[account_columns]\nnumber = \"E\"").is_ok());
+        assert!(Schema::load("test_missing_schema.toml")?.validate().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cell() {
+        assert_eq!(cell("A", 2), "A2");
+        assert_eq!(cell("AB", 10u32), "AB10");
+    }
+}
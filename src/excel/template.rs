@@ -0,0 +1,283 @@
+use std::error;
+use std::io::Cursor;
+use std::path::Path;
+use umya_spreadsheet::{reader, Worksheet};
+
+use super::reading::{self, Categories};
+use super::schema::{cell, Schema};
+use crate::accounting;
+use crate::file;
+
+// the workbook new workbooks are made from, built into the program so it works from any folder
+pub const TEMPLATE: &[u8] = include_bytes!("../templates/template.xlsx");
+
+// the languages a new workbook can be made in, the same as the month languages
+pub const LANGUAGES: [&str; 2] = ["norsk", "english"];
+
+// the schema of a workbook made from the template, the sheets are named in the language
+pub fn schema(language: &str) -> Schema {
+    let (transaction_sheet, category_sheet, account_sheet) = match language {
+        "english" => ("Transactions", "Categories", "Information"),
+        _ => ("Kontoutskrift", "Kategorier", "Informasjon"),
+    };
+    Schema {
+        transaction_sheet: String::from(transaction_sheet),
+        category_sheet: String::from(category_sheet),
+        account_sheet: String::from(account_sheet),
+        ..Schema::default()
+    }
+}
+
+// the headers of the transaction sheet, in the order of the default columns
+fn transaction_headers(language: &str) -> [&'static str; 17] {
+    match language {
+        "english" => [
+            "ACCOUNTING DATE",
+            "INTEREST DATE",
+            "ARCHIVE REFERENCE",
+            "COUNTER ACCOUNT",
+            "TYPE",
+            "TEXT",
+            "OUT OF ACCOUNT",
+            "INTO ACCOUNT",
+            "AMOUNT",
+            "ACCOUNT",
+            "GROUP",
+            "INCOME/EXPENSE",
+            "YEAR",
+            "MONTH",
+            "NOTE",
+            "CURRENCY",
+            "ORIGINAL AMOUNT",
+        ],
+        _ => [
+            "BOKFØRINGSDATO",
+            "RENTEDATO",
+            "ARKIVREFERANSE",
+            "MOTKONTO",
+            "TYPE",
+            "TEKST",
+            "UT FRA KONTO",
+            "INN PÅ KONTO",
+            "BELØP",
+            "KONTO",
+            "GRUPPE",
+            "INNTEKT/KOSTNAD",
+            "ÅR",
+            "MÅNED",
+            "NOTAT",
+            "VALUTA",
+            "ORIGINALBELØP",
+        ],
+    }
+}
+
+// a few common categories to start from, [group, income/expense, key words...]
+pub fn starter_categories(language: &str) -> Categories {
+    let category = |words: &[&str]| words.iter().map(|w| String::from(*w)).collect();
+    match language {
+        "english" => Categories::new(
+            vec![
+                category(&["Food", "Expense", "kiwi", "rema", "coop", "meny", "extra"]),
+                category(&["Transport", "Expense", "ruter", "vy", "circle k", "esso"]),
+                category(&["Housing", "Expense", "rent", "fjordkraft", "tibber"]),
+                category(&["Subscriptions", "Expense", "spotify", "netflix", "telenor"]),
+            ],
+            vec![
+                category(&["Salary", "Income"]),
+                category(&["Interest", "Income"]),
+            ],
+        ),
+        _ => Categories::new(
+            vec![
+                category(&["Mat", "Kostnad", "kiwi", "rema", "coop", "meny", "extra"]),
+                category(&["Transport", "Kostnad", "ruter", "vy", "circle k", "esso"]),
+                category(&["Bolig", "Kostnad", "husleie", "fjordkraft", "tibber"]),
+                category(&["Abonnement", "Kostnad", "spotify", "netflix", "telenor"]),
+            ],
+            vec![
+                category(&["Lønn", "Inntekt"]),
+                category(&["Renter", "Inntekt"]),
+            ],
+        ),
+    }
+}
+
+// makes a workbook from the template with the accounts and the starter categories, an existing
+// file is never overwritten, returns the schema of the new workbook
+pub fn new_workbook(
+    path: &str,
+    language: &str,
+    accounts: &[accounting::lib::Account],
+) -> Result<Schema, Box<dyn error::Error>> {
+    if Path::new(path).exists() {
+        return Err(format!("{} already exists", path).into());
+    }
+    let schema = schema(language);
+    let mut book = reader::xlsx::read_reader(Cursor::new(TEMPLATE), true)
+        .map_err(|e| format!("could not read the template: {:?}", e))?;
+
+    // the template names the sheets in norwegian
+    let default = Schema::default();
+    for (from, to) in [
+        (&default.transaction_sheet, &schema.transaction_sheet),
+        (&default.category_sheet, &schema.category_sheet),
+        (&default.account_sheet, &schema.account_sheet),
+    ] {
+        reading::open_sheet_mut(&mut book, path, from)?.set_name(to);
+    }
+
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    clear_rows(sheet, schema.first_row());
+    for ((_, column), header) in schema
+        .columns
+        .fields()
+        .into_iter()
+        .zip(transaction_headers(language))
+    {
+        sheet
+            .get_cell_mut(&cell(column, schema.header_row))
+            .set_value(header);
+    }
+
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.category_sheet)?;
+    write_categories(sheet, language);
+
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.account_sheet)?;
+    write_accounts(sheet, &schema, language, accounts);
+
+    file::lib::save_file(&book, path)?;
+    Ok(schema)
+}
+
+// the category sheet is read column by column, the text categories first and then the type
+// categories, starting with a column that has "type" in the first row
+fn write_categories(sheet: &mut Worksheet, language: &str) {
+    clear_rows(sheet, 1);
+    let (from_text, from_type) = match language {
+        "english" => ("From text", "From type"),
+        _ => ("Hentes fra tekst", "Hentes fra type"),
+    };
+    let categories = starter_categories(language);
+    let mut col = 1;
+    for (header, categories) in [
+        (from_text, &categories.from_text),
+        (from_type, &categories.from_type),
+    ] {
+        sheet
+            .get_cell_by_column_and_row_mut(&col, &1)
+            .set_value(header);
+        for category in categories {
+            // the group on row 2, income/expense on row 3 and the key words from row 4
+            for (row, value) in category.iter().enumerate() {
+                sheet
+                    .get_cell_by_column_and_row_mut(&col, &(row as u32 + 2))
+                    .set_value(value);
+            }
+            col += 1;
+        }
+    }
+}
+
+fn write_accounts(
+    sheet: &mut Worksheet,
+    schema: &Schema,
+    language: &str,
+    accounts: &[accounting::lib::Account],
+) {
+    clear_rows(sheet, schema.first_row());
+    let columns = &schema.account_columns;
+    let (name, number, currency) = match language {
+        "english" => ("Account name", "Account number", "Currency"),
+        _ => ("Kontonavn", "Kontonummer", "Valuta"),
+    };
+    for (column, header) in [
+        (&columns.name, name),
+        (&columns.number, number),
+        (&columns.currency, currency),
+    ] {
+        sheet
+            .get_cell_mut(&cell(column, schema.header_row))
+            .set_value(header);
+    }
+
+    // the first column lists the sheets of the workbook
+    for (i, sheet_name) in [
+        &schema.transaction_sheet,
+        &schema.category_sheet,
+        &schema.account_sheet,
+    ]
+    .iter()
+    .enumerate()
+    {
+        sheet
+            .get_cell_by_column_and_row_mut(&1, &(schema.first_row() + i as u32))
+            .set_value(sheet_name.as_str());
+    }
+
+    for (i, account) in accounts.iter().enumerate() {
+        let row = schema.first_row() + i as u32;
+        sheet
+            .get_cell_mut(&cell(&columns.name, row))
+            .set_value_from_string(&account.name);
+        sheet
+            .get_cell_mut(&cell(&columns.number, row))
+            .set_value_from_u64(account.number);
+        sheet
+            .get_cell_mut(&cell(&columns.currency, row))
+            .set_value_from_string(accounting::currency::normalize(&account.currency));
+    }
+}
+
+// removes the example rows of the template
+fn clear_rows(sheet: &mut Worksheet, from_row: u32) {
+    let (highest_column, highest_row) = sheet.get_highest_column_and_row();
+    for row in from_row..=highest_row {
+        for col in 1..=highest_column {
+            sheet.remove_cell_by_column_and_row_mut(&col, &row);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_template {
+    use super::*;
+
+    #[test]
+    fn test_new_workbook() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_new_workbook.xlsx";
+        let accounts = vec![accounting::lib::Account {
+            name: String::from("Savings"),
+            number: 12345678901,
+            currency: String::from("eur"),
+        }];
+        let schema = new_workbook(path, "english", &accounts);
+        let read = schema.and_then(|schema| {
+            Ok((
+                reading::get_accounts(path, &schema)?,
+                reading::get_categories(path, &schema)?,
+                reading::get_workbook_transactions(path, &schema)?,
+                // never overwrites a workbook
+                new_workbook(path, "english", &accounts).is_err(),
+            ))
+        });
+        std::fs::remove_file(path)?;
+        let (read_accounts, categories, transactions, exists) = read?;
+
+        assert_eq!(read_accounts.len(), 1);
+        assert_eq!(read_accounts[0].name, "Savings");
+        assert_eq!(read_accounts[0].number, 12345678901);
+        assert_eq!(read_accounts[0].currency, "EUR");
+        assert_eq!(
+            categories.from_text,
+            starter_categories("english").from_text
+        );
+        assert_eq!(
+            categories.from_type,
+            starter_categories("english").from_type
+        );
+        assert!(transactions.is_empty());
+        assert!(exists);
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 use super::reading;
-use super::workbook;
+use crate::accounting::currency::ExchangeRates;
+use crate::accounting::money::Money;
+use crate::accounting::transaction::{Transaction, Transactions};
 use chrono::NaiveDate;
 use std::fs;
 use std::sync::Once;
@@ -23,14 +25,16 @@ pub fn initialize_tests() -> Result<(), Box<dyn std::error::Error>> {
         Err(_) => (),
     };
     // copies the template and make it a test files
-    fs::copy("src/templates/template.xlsx", "test.xlsx").map_err(|e| format!("could not copy file: {:?}", e))?;
+    fs::copy("src/templates/template.xlsx", "test.xlsx")
+        .map_err(|e| format!("could not copy file: {:?}", e))?;
     Ok(())
 }
 
 pub struct MockTransaction {
     pub path: String,
-    pub info: workbook::WorkbookInfo,
+    pub info: Transactions,
     pub categories: reading::Categories,
+    pub rates: ExchangeRates,
     pub date_delimiter: String,
     pub date_month_style: String,
     pub date_language: String,
@@ -39,42 +43,23 @@ pub struct MockTransaction {
 
 pub fn create_mock_transactions(size: u32) -> Result<MockTransaction, Box<dyn std::error::Error>> {
     let path = String::from("test.xlsx");
-    let mut accounting_date = vec![];
-    let mut interest_date = vec![];
-    let mut archive_reference = vec![];
-    let mut counter_account = vec![];
-    let mut types = vec![];
-    let mut text = vec![];
-    let mut out_of_account = vec![];
-    let mut into_account = vec![];
-    let mut account = vec![];
+    let mut info = Transactions::new();
     for i in 0..size {
-        accounting_date.push(NaiveDate::from_num_days_from_ce(i as i32));
-        interest_date.push(NaiveDate::from_num_days_from_ce(i as i32));
-        archive_reference.push(String::from("0"));
-        counter_account.push(String::from("1"));
-        types.push(String::from("types"));
-        text.push(String::from("text"));
-        out_of_account.push(0.0);
-        into_account.push(0.0);
-        account.push(String::from("0"))
+        info.push(Transaction {
+            archive_reference: String::from("0"),
+            counter_account: String::from("1"),
+            types: String::from("types"),
+            text: String::from("text"),
+            account: String::from("0"),
+            ..Transaction::new(NaiveDate::from_num_days_from_ce(i as i32), Money::ZERO)
+        });
     }
-    let info = workbook::WorkbookInfo::new(
-        accounting_date,
-        interest_date,
-        archive_reference,
-        counter_account,
-        types,
-        text,
-        out_of_account,
-        into_account,
-        account,
-    )?;
 
     let mock_transaction = MockTransaction {
         path: path,
         info: info,
         categories: reading::Categories::new(vec![], vec![]),
+        rates: ExchangeRates::default(),
         date_delimiter: String::from("/"),
         date_month_style: String::from("short"),
         date_language: String::from("english"),
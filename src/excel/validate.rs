@@ -0,0 +1,309 @@
+use std::error;
+use std::fmt;
+use umya_spreadsheet::helper::coordinate::string_from_column_index;
+use umya_spreadsheet::Worksheet;
+
+use super::lib;
+use super::reading;
+use super::rules::Rule;
+use super::schema::{cell, Schema};
+use crate::accounting::money::Money;
+use crate::file;
+
+// Something in the workbook the readers would stop at or read wrong, cell is empty if the
+// problem is the whole sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub sheet: String,
+    pub cell: String,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(sheet: &str, cell: &str, message: impl ToString) -> Self {
+        Self {
+            sheet: String::from(sheet),
+            cell: String::from(cell),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.cell.is_empty() {
+            write!(f, "{}: {}", self.sheet, self.message)
+        } else {
+            write!(f, "{}!{}: {}", self.sheet, self.cell, self.message)
+        }
+    }
+}
+
+// checks the whole workbook against the schema, an empty list means the readers can read it,
+// only a schema or file that cannot be opened is an error
+pub fn validate_workbook(
+    path: &str,
+    schema: &Schema,
+) -> Result<Vec<Problem>, Box<dyn error::Error>> {
+    schema.validate()?;
+    let book = file::lib::open_file(path)?;
+
+    let mut problems = vec![];
+    for (name, check) in [
+        (
+            &schema.transaction_sheet,
+            check_transactions as fn(&Worksheet, &Schema) -> Vec<Problem>,
+        ),
+        (&schema.category_sheet, check_categories),
+        (&schema.account_sheet, check_accounts),
+    ] {
+        match reading::open_sheet(&book, path, name) {
+            Ok(sheet) => problems.append(&mut check(sheet, schema)),
+            Err(_) => problems.push(Problem::new(name, "", "the sheet is missing")),
+        }
+    }
+    Ok(problems)
+}
+
+fn check_transactions(sheet: &Worksheet, schema: &Schema) -> Vec<Problem> {
+    let name = &schema.transaction_sheet;
+    let columns = &schema.columns;
+    let mut problems = vec![];
+    let (_, highest_row) = sheet.get_highest_column_and_row();
+
+    let mut row = schema.first_row();
+    // the transactions end on the first row without an accounting date
+    while !sheet
+        .get_value(&cell(&columns.accounting_date, row))
+        .is_empty()
+    {
+        for (column, field) in [
+            (&columns.accounting_date, "accounting date"),
+            (&columns.interest_date, "interest date"),
+        ] {
+            let coordinate = cell(column, row);
+            let value = sheet.get_value(&coordinate);
+            if lib::cell_to_date(&value).is_err() {
+                problems.push(Problem::new(
+                    name,
+                    &coordinate,
+                    format!("the {} '{}' is not a date", field, value),
+                ));
+            }
+        }
+        for (column, field) in [
+            (&columns.out_of_account, "out of account"),
+            (&columns.into_account, "into account"),
+        ] {
+            let coordinate = cell(column, row);
+            let value = sheet.get_value(&coordinate);
+            if Money::parse(&value).is_err() {
+                problems.push(Problem::new(
+                    name,
+                    &coordinate,
+                    format!("the {} amount '{}' is not a number", field, value),
+                ));
+            }
+        }
+        row += 1;
+    }
+
+    // rows after the gap are never read
+    let last = row;
+    let fields = columns.fields();
+    for r in last..=highest_row {
+        if fields
+            .iter()
+            .any(|(_, column)| !sheet.get_value(&cell(column, r)).is_empty())
+        {
+            problems.push(Problem::new(
+                name,
+                &cell(&schema.columns.accounting_date, last),
+                format!(
+                    "the row has no accounting date, the rows from {} are not read",
+                    r
+                ),
+            ));
+            break;
+        }
+    }
+    problems
+}
+
+fn check_categories(sheet: &Worksheet, schema: &Schema) -> Vec<Problem> {
+    let name = &schema.category_sheet;
+    let mut problems = vec![];
+    let (highest_column, _) = sheet.get_highest_column_and_row();
+
+    let mut col = 1;
+    // the categories end on the first column without a group on row 2
+    while !sheet.get_value_by_column_and_row(&col, &2).is_empty() {
+        if sheet.get_value_by_column_and_row(&col, &3).is_empty() {
+            problems.push(Problem::new(
+                name,
+                &coordinate(col, 3),
+                format!(
+                    "the category '{}' has no income/expense, it is not used",
+                    sheet.get_value_by_column_and_row(&col, &2)
+                ),
+            ));
+        } else if sheet.get_value_by_column_and_row(&col, &4).is_empty()
+            && !sheet.get_value_by_column_and_row(&col, &5).is_empty()
+        {
+            problems.push(Problem::new(
+                name,
+                &coordinate(col, 4),
+                "the key words start on row 4, the ones after the empty cell are not used",
+            ));
+        }
+        let mut row = 4;
+        while !sheet.get_value_by_column_and_row(&col, &row).is_empty() {
+            let keyword = sheet.get_value_by_column_and_row(&col, &row);
+            if let Err(e) = Rule::from_keyword("", "", &keyword) {
+                problems.push(Problem::new(
+                    name,
+                    &coordinate(col, row),
+                    format!("{}, it is looked for as text", e),
+                ));
+            }
+            row += 1;
+        }
+        col += 1;
+    }
+
+    let last = col;
+    for col in last + 1..=highest_column {
+        if !sheet.get_value_by_column_and_row(&col, &2).is_empty() {
+            problems.push(Problem::new(
+                name,
+                &coordinate(last, 2),
+                format!(
+                    "the column has no group, the categories from column {} are not read",
+                    string_from_column_index(&col)
+                ),
+            ));
+            break;
+        }
+    }
+    problems
+}
+
+fn check_accounts(sheet: &Worksheet, schema: &Schema) -> Vec<Problem> {
+    let name = &schema.account_sheet;
+    let columns = &schema.account_columns;
+    let mut problems = vec![];
+
+    let mut row = schema.first_row();
+    // the accounts end on the first row without a name
+    while !sheet.get_value(&cell(&columns.name, row)).is_empty() {
+        let coordinate = cell(&columns.number, row);
+        let number = sheet.get_value(&coordinate);
+        if number.trim().parse::<u64>().is_err() {
+            problems.push(Problem::new(
+                name,
+                &coordinate,
+                format!("the account number '{}' is not a number", number),
+            ));
+        }
+        let coordinate = cell(&columns.currency, row);
+        let currency = sheet.get_value(&coordinate);
+        if !currency.is_empty() && !valid_currency(&currency) {
+            problems.push(Problem::new(
+                name,
+                &coordinate,
+                format!(
+                    "the currency '{}' is not a currency code like NOK",
+                    currency
+                ),
+            ));
+        }
+        row += 1;
+    }
+    if !sheet.get_value(&cell(&columns.number, row)).is_empty() {
+        problems.push(Problem::new(
+            name,
+            &cell(&columns.name, row),
+            "the account has a number but no name, it is not read",
+        ));
+    }
+    problems
+}
+
+fn coordinate(col: u32, row: u32) -> String {
+    cell(&string_from_column_index(&col), row)
+}
+
+fn valid_currency(currency: &str) -> bool {
+    let currency = currency.trim();
+    currency.len() == 3 && currency.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests_validate {
+    use super::*;
+    use crate::accounting;
+    use crate::excel::template;
+
+    #[test]
+    fn test_validate_workbook() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_validate.xlsx";
+        let _ = std::fs::remove_file(path);
+        let accounts = vec![accounting::lib::Account {
+            name: String::from("Main"),
+            number: 1234,
+            currency: String::from("NOK"),
+        }];
+        let schema = template::new_workbook(path, "norsk", &accounts)?;
+        let clean = validate_workbook(path, &schema);
+
+        let mut book = file::lib::open_file(path)?;
+        let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+        for (coordinate, value) in [
+            ("A2", "44715"),
+            ("B2", "03.06.2022"),
+            ("G2", "12,50"),
+            ("H2", "0"),
+            ("A3", "32.13.2022"),
+            ("B3", "44715"),
+            ("G3", "0"),
+            ("H3", "1"),
+            ("F5", "after the gap"),
+        ] {
+            sheet.get_cell_mut(coordinate).set_value(value);
+        }
+        let sheet = reading::open_sheet_mut(&mut book, path, &schema.category_sheet)?;
+        sheet.get_cell_mut("A3").set_value("");
+        sheet.get_cell_mut("B5").set_value("re:(ruter");
+        let sheet = reading::open_sheet_mut(&mut book, path, &schema.account_sheet)?;
+        sheet.get_cell_mut("C2").set_value("12a");
+        sheet.get_cell_mut("D2").set_value("kroner");
+        file::lib::save_file(&book, path)?;
+        let problems = validate_workbook(path, &schema);
+        let missing = validate_workbook(path, &template::schema("english"));
+        std::fs::remove_file(path)?;
+
+        assert!(clean?.is_empty());
+        let cells: Vec<String> = problems?
+            .iter()
+            .map(|p| format!("{}!{}", p.sheet, p.cell))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                "Kontoutskrift!G2",
+                "Kontoutskrift!A3",
+                "Kontoutskrift!A4",
+                "Kategorier!A3",
+                "Kategorier!B5",
+                "Informasjon!C2",
+                "Informasjon!D2",
+            ]
+        );
+        assert_eq!(missing?.len(), 3);
+        assert_eq!(
+            Problem::new("Informasjon", "", "the sheet is missing").to_string(),
+            "Informasjon: the sheet is missing"
+        );
+        Ok(())
+    }
+}
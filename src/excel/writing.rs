@@ -1,241 +1,469 @@
-use chrono::Datelike;
+use chrono::{Datelike, NaiveDate};
 use std::error;
-use umya_spreadsheet::*;
+use umya_spreadsheet::Worksheet;
 
+use super::classifier::Classifier;
 use super::lib;
 use super::reading;
-use super::workbook;
+use super::schema::{cell, Schema};
 use crate::accounting;
+use crate::accounting::currency::ExchangeRates;
+use crate::accounting::money::Money;
+use crate::accounting::transaction::{Transaction, Transactions};
+use crate::error::{Error, Location};
 use crate::file;
 
-pub fn write_to_workbook(
+// Called once before an operation saves the workbook, e.g. to back it up, an operation that
+// changes nothing does not save and does not call it
+pub type BeforeSave<'a> = &'a mut dyn FnMut() -> Result<(), Box<dyn error::Error>>;
+
+// Where and how write puts the rows, the layout of the workbook, the rates to the reporting
+// currency and how the dates and months are shown
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOptions<'a> {
+    pub schema: &'a Schema,
+    pub rates: &'a ExchangeRates,
+    pub date_delimiter: &'a str,
+    pub date_month_style: &'a str,
+    pub date_language: &'a str,
+    pub date_capitalize: bool,
+}
+
+// The new transactions of an import, with the probable duplicates that has to be accepted
+// or rejected before they are written
+#[derive(Debug, Clone, Default)]
+pub struct PendingImport {
+    pub transactions: Transactions,
+    // the transactions of the export that are already in the workbook
+    pub duplicates: Transactions,
+    pub suspects: Vec<lib::Suspect>,
+    // the rows of the export a lenient import could not read
+    pub skipped_rows: Vec<accounting::bank::SkippedRow>,
+    // the transactions of the export, and the rows of the account in the workbook once every
+    // new one is written, checked against the balances of the statement, none if it has none
+    pub statement_check: Option<accounting::bank::Reconciliation>,
+    pub workbook_check: Option<accounting::bank::Reconciliation>,
+}
+
+impl PendingImport {
+    // the transactions to write, accepted has one element per suspect
+    pub fn accepted(&self, accepted: &[bool]) -> Transactions {
+        let rejected = self.rejected_indexes(accepted);
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !rejected.contains(i))
+            .map(|(_, transaction)| transaction.clone())
+            .collect()
+    }
+
+    // the transactions that are skipped, the duplicates first and then the rejected suspects
+    pub fn skipped(&self, accepted: &[bool]) -> Transactions {
+        let mut skipped = self.duplicates.clone();
+        for i in self.rejected_indexes(accepted) {
+            skipped.push(self.transactions[i].clone());
+        }
+        skipped
+    }
+
+    fn rejected_indexes(&self, accepted: &[bool]) -> Vec<usize> {
+        self.suspects
+            .iter()
+            .zip(accepted)
+            .filter(|(_, accepted)| !**accepted)
+            .map(|(suspect, _)| suspect.index)
+            .collect()
+    }
+
+    // what was checked against the balances of the statement, and the result
+    pub fn balance_checks(&self) -> Vec<(&'static str, accounting::bank::Reconciliation)> {
+        [
+            ("The statement", &self.statement_check),
+            ("The workbook after the import", &self.workbook_check),
+        ]
+        .into_iter()
+        .filter_map(|(name, check)| check.clone().map(|check| (name, check)))
+        .collect()
+    }
+}
+
+// A row an import would append, with the group write would give it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewRow {
+    pub transaction: Transaction,
+    pub group: String,
+    pub income_expense: String,
+}
+
+// what write would append to the workbook, without changing it
+pub fn preview(transactions: &Transactions, categories: &reading::Categories) -> Vec<PreviewRow> {
+    transactions
+        .iter()
+        .map(|transaction| {
+            let (group, income_expense) = lib::transaction_category(transaction, categories);
+            PreviewRow {
+                transaction: transaction.clone(),
+                group,
+                income_expense,
+            }
+        })
+        .collect()
+}
+
+// reads the transactions that are not in the workbook, and flags the ones that are
+// similar to a workbook row booked at most days apart, a lenient import skips the rows
+// of the export it cannot read
+pub fn prepare_import(
     workbook_path: &str,
     transaction_path: &str,
-    bank: accounting::bank::Bank,
-    account: &str,
-    categories: &reading::Categories,
-    date_delimiter: &str,
-    date_month_style: &str,
-    date_language: &str,
-    date_capitalize: &bool,
-) -> Result<(), Box<dyn error::Error>> {
-    match reading::get_transactions(&transaction_path, bank, account) {
-        Ok(transaction_info) => {
-            let workbook_info = reading::get_workbook_transactions(&workbook_path)?;
-            let transaction_info = lib::remove_duplicates(transaction_info, workbook_info);
-            write(
-                &workbook_path,
-                transaction_info,
-                categories,
-                date_delimiter,
-                date_month_style,
-                date_language,
-                date_capitalize,
+    importer: &dyn accounting::bank::BankImporter,
+    account: &accounting::lib::Account,
+    schema: &Schema,
+    days: i64,
+    lenient: bool,
+) -> Result<PendingImport, Box<dyn error::Error>> {
+    let (transactions, skipped_rows) =
+        reading::get_transactions(transaction_path, importer, account, lenient)?;
+    let workbook = reading::get_workbook_transactions(workbook_path, schema)?;
+    let (transactions, duplicates) = lib::split_duplicates(transactions, workbook.clone());
+    let suspects = lib::find_suspects(&transactions, &workbook, days);
+
+    let (statement_check, workbook_check) = match importer.statement(transaction_path)? {
+        Some(statement) => {
+            let mut imported = workbook;
+            for transaction in transactions.iter() {
+                imported.push(transaction.clone());
+            }
+            (
+                statement.reconcile().ok(),
+                statement.reconcile_workbook(&imported, &account.name).ok(),
             )
         }
-        Err(e) => Err(e),
-    }
+        None => (None, None),
+    };
+    Ok(PendingImport {
+        transactions,
+        duplicates,
+        suspects,
+        skipped_rows,
+        statement_check,
+        workbook_check,
+    })
 }
 
 pub fn write(
     path: &str,
-    info: workbook::WorkbookInfo,
+    transactions: Transactions,
     categories: &reading::Categories,
-    date_delimiter: &str,
-    date_month_style: &str,
-    date_language: &str,
-    date_capitalize: &bool,
+    options: &WriteOptions,
+    before_save: BeforeSave,
 ) -> Result<(), Box<dyn error::Error>> {
-    let mut book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name_mut("Kontoutskrift")
-        .map_err(|e| format!("could not open worksheet 'Kontoutskrift': {:?}", e))?;
+    let WriteOptions {
+        schema,
+        rates,
+        date_delimiter,
+        date_month_style,
+        date_language,
+        date_capitalize,
+    } = *options;
+    schema.validate()?;
+    if transactions.is_empty() {
+        return Ok(());
+    }
+    let mut book = file::lib::open_file(path)?;
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    let columns = &schema.columns;
+
+    // older workbooks do not have the currency and original amount columns
+    for (column, header) in [
+        (&columns.currency, "VALUTA"),
+        (&columns.original_amount, "ORIGINALBELØP"),
+    ] {
+        let header_cell = cell(column, schema.header_row);
+        if sheet.get_value(&header_cell).is_empty() {
+            sheet.get_cell_mut(&header_cell).set_value(header);
+        }
+    }
 
     // start on the first empty line
-    let mut row = reading::get_first_empty_line(sheet);
-    for i in 0..info.accounting_date.len() {
+    let mut row = reading::get_first_empty_line(sheet, schema);
+    for transaction in transactions.iter() {
         // accounting date
-        let accounting_date_str = lib::date_to_string(info.accounting_date[i], date_delimiter);
-        sheet
-            .get_cell_mut(&(String::from("A") + &row.to_string()))
-            .set_value(accounting_date_str);
+        set_date(
+            sheet,
+            &cell(&columns.accounting_date, row),
+            transaction.accounting_date,
+            date_delimiter,
+        );
 
         // interest date
-        let interest_date_str = lib::date_to_string(info.interest_date[i], date_delimiter);
-        sheet
-            .get_cell_mut(&(String::from("B") + &row.to_string()))
-            .set_value(interest_date_str);
+        set_date(
+            sheet,
+            &cell(&columns.interest_date, row),
+            transaction.interest_date,
+            date_delimiter,
+        );
 
         // archive reference
         sheet
-            .get_cell_mut(&(String::from("C") + &row.to_string()))
-            .set_value(&info.archive_reference[i]);
+            .get_cell_mut(&cell(&columns.archive_reference, row))
+            .set_value(&transaction.archive_reference);
 
         // counter account
         sheet
-            .get_cell_mut(&(String::from("D") + &row.to_string()))
-            .set_value(&info.counter_account[i]);
+            .get_cell_mut(&cell(&columns.counter_account, row))
+            .set_value(&transaction.counter_account);
 
         // type
         sheet
-            .get_cell_mut(&(String::from("E") + &row.to_string()))
-            .set_value(&info.types[i]);
+            .get_cell_mut(&cell(&columns.types, row))
+            .set_value(&transaction.types);
 
         // text
         sheet
-            .get_cell_mut(&(String::from("F") + &row.to_string()))
-            .set_value(&info.text[i]);
-
-        // out of account
-        sheet
-            .get_cell_mut(&(String::from("G") + &row.to_string()))
-            .set_value(&info.out_of_account[i].to_string());
+            .get_cell_mut(&cell(&columns.text, row))
+            .set_value(&transaction.text);
+
+        // out of account, in the currency of the transaction
+        let currency = accounting::currency::normalize(&transaction.currency);
+        set_amount(
+            sheet,
+            &cell(&columns.out_of_account, row),
+            transaction.out_of_account,
+            &currency,
+        );
 
         // into account
-        sheet
-            .get_cell_mut(&(String::from("H") + &row.to_string()))
-            .set_value(&info.into_account[i].to_string());
+        set_amount(
+            sheet,
+            &cell(&columns.into_account, row),
+            transaction.into_account,
+            &currency,
+        );
 
-        // amount
-        let amount = &info.into_account[i] - &info.out_of_account[i];
-        sheet
-            .get_cell_mut(&(String::from("I") + &row.to_string()))
-            .set_value(amount.to_string());
+        // amount, in the reporting currency so the sums of the workbook add up
+        let amount = rates
+            .convert(
+                transaction.amount(),
+                &transaction.currency,
+                transaction.accounting_date,
+            )
+            .map_err(|e| format!("could not convert '{}': {:?}", transaction.text, e))?;
+        set_amount(
+            sheet,
+            &cell(&columns.amount, row),
+            amount,
+            &rates.reporting_currency,
+        );
 
         // account
         sheet
-            .get_cell_mut(&(String::from("J") + &row.to_string()))
-            .set_value(&info.account[i]);
+            .get_cell_mut(&cell(&columns.account, row))
+            .set_value(&transaction.account);
 
-        // group
-        let category = lib::get_category(&info.text[i], &info.types[i], categories);
+        // group, the category from the export is used if it has one
+        let category = lib::transaction_category(transaction, categories);
         sheet
-            .get_cell_mut(&(String::from("K") + &row.to_string()))
+            .get_cell_mut(&cell(&columns.group, row))
             .set_value(category.0);
 
         // income/expense
         sheet
-            .get_cell_mut(&(String::from("L") + &row.to_string()))
+            .get_cell_mut(&cell(&columns.income_expense, row))
             .set_value(category.1);
 
         // year
-        let year = info.accounting_date[i].format("%Y").to_string();
+        let year = transaction.accounting_date.format("%Y").to_string();
         sheet
-            .get_cell_mut(&(String::from("M") + &row.to_string()))
+            .get_cell_mut(&cell(&columns.year, row))
             .set_value(year);
 
         // month
         let month = lib::get_month(
-            info.accounting_date[i].month(),
+            transaction.accounting_date.month(),
             date_month_style,
             date_language,
             &date_capitalize,
         );
         sheet
-            .get_cell_mut(&(String::from("N") + &row.to_string()))
+            .get_cell_mut(&cell(&columns.month, row))
             .set_value(month);
+
+        // currency
+        sheet
+            .get_cell_mut(&cell(&columns.currency, row))
+            .set_value(currency);
+
+        // original amount, only if it was paid in another currency
+        if !transaction.original_currency.is_empty() {
+            set_amount(
+                sheet,
+                &cell(&columns.original_amount, row),
+                transaction.original_amount,
+                &accounting::currency::normalize(&transaction.original_currency),
+            );
+        }
         row += 1;
     }
 
-    let _ = writer::xlsx::write(&book, path);
-    Ok(())
+    before_save()?;
+    Ok(file::lib::save_file(&book, path)?)
 }
 
-pub fn fill_empty_rows(path: &str, range: u32, margin: u32) -> Result<(), Box<dyn error::Error>> {
-    let mut book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name_mut("Kontoutskrift")
-        .map_err(|e| format!("could not open worksheet 'Kontoutskrift': {:?}", e))?;
+// the parts of a row the category rules look at
+fn row_transaction(sheet: &Worksheet, schema: &Schema, row: u32) -> Transaction {
+    let columns = &schema.columns;
+    let value = |column: &str| sheet.get_value(&cell(column, row));
+    let amount = |column: &str| Money::parse(&value(column)).unwrap_or(Money::ZERO);
+    Transaction {
+        counter_account: value(&columns.counter_account),
+        types: value(&columns.types),
+        text: value(&columns.text),
+        out_of_account: amount(&columns.out_of_account),
+        into_account: amount(&columns.into_account),
+        account: value(&columns.account),
+        ..Transaction::new(
+            lib::cell_to_date(&value(&columns.accounting_date)).unwrap_or(lib::excel_epoch()),
+            Money::ZERO,
+        )
+    }
+}
 
-    // Start on row 2
-    let mut row = 1;
+// writes the date as an excel date, shown with the delimiter, false if the cell already was
+fn set_date(sheet: &mut Worksheet, coordinate: &str, date: NaiveDate, delimiter: &str) -> bool {
+    let serial = lib::date_to_serial(date);
+    let format = lib::date_format(delimiter);
+    if sheet.get_value(coordinate) == serial.to_string()
+        && sheet
+            .get_style(coordinate)
+            .get_number_format()
+            .as_ref()
+            .is_some_and(|number_format| number_format.get_format_code() == format)
+    {
+        return false;
+    }
+    let cell = sheet.get_cell_mut(coordinate);
+    cell.set_value_from_i64(serial);
+    cell.get_style_mut()
+        .get_number_format_mut()
+        .set_format_code(format);
+    true
+}
+
+// writes the amount as a number, so sums and pivot tables work without converting it
+fn set_amount(sheet: &mut Worksheet, coordinate: &str, amount: Money, currency: &str) {
+    let cell = sheet.get_cell_mut(coordinate);
+    cell.get_cell_value_mut()
+        .set_value_from_numberic(amount.to_f64());
+    cell.get_style_mut()
+        .get_number_format_mut()
+        .set_format_code(lib::amount_format(currency));
+}
+
+pub fn fill_empty_rows(
+    path: &str,
+    schema: &Schema,
+    range: u32,
+    margin: u32,
+    before_save: BeforeSave,
+) -> Result<(), Box<dyn error::Error>> {
+    schema.validate()?;
+    let mut book = file::lib::open_file(path)?;
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    let columns = &schema.columns;
+    let mut changed = false;
+
+    // Start on the row after the header
+    let mut row = schema.header_row;
     loop {
         row += 1;
         // check if it is valid (accounting date is not empty), if empty, it is finished and break
-        if sheet.get_value(&(String::from("A") + &row.to_string())) == "" {
+        if sheet.get_value(&cell(&columns.accounting_date, row)) == "" {
             break;
         };
         // check if group is none, else continue
-        if sheet.get_value(&(String::from("K") + &row.to_string())) != "" {
+        if sheet.get_value(&cell(&columns.group, row)) != "" {
             continue;
         };
 
         let mut start: i32 = row as i32 - range as i32;
-        // check if the start of the check is less than 1
-        if start < 1 {
-            start = 1;
+        // check if the start of the check is before the first row
+        if start < schema.first_row() as i32 {
+            start = schema.first_row() as i32;
         }
         let end = row as i32 + range as i32;
 
-        let row_value = sheet
-            .get_value(&(String::from("I") + &row.to_string()))
-            .parse::<f32>()
-            .map_err(|e| format!("row value is not a float: {:?}", e))?;
+        let value = sheet.get_value(&cell(&columns.amount, row));
+        let row_value = Money::parse(&value).map_err(|_| Error::BadAmount {
+            file: String::from(path),
+            location: Location::cell(&schema.transaction_sheet, &cell(&columns.amount, row)),
+            value,
+        })?;
+        let margin = Money::from_kroner(margin as i64);
         for r in start..end {
             // check if it is valid (accounting date is not empty), if empty, it is finished and break
-            if sheet.get_value(&(String::from("A") + &r.to_string())) == "" {
+            if sheet.get_value(&cell(&columns.accounting_date, r)) == "" {
                 break;
             };
             // check if group exist, else continue
-            let group = sheet.get_value(&(String::from("K") + &r.to_string()));
+            let group = sheet.get_value(&cell(&columns.group, r));
             if group == "" {
                 continue;
             };
 
-            let r_value: f32 = match sheet
-                .get_value(&(String::from("I") + &r.to_string()))
-                .parse::<f32>()
-            {
+            let r_value = match Money::parse(&sheet.get_value(&cell(&columns.amount, r))) {
                 Ok(value) => value,
                 Err(_) => continue,
             };
             // compare it to -r_value, because want to get the transactions that close each other
-            if row_value as f32 - margin as f32 <= -r_value
-                && row_value as f32 + margin as f32 >= -r_value
-            {
+            if row_value - margin <= -r_value && row_value + margin >= -r_value {
                 sheet
-                    .get_cell_mut(&(String::from("K") + &row.to_string()))
+                    .get_cell_mut(&cell(&columns.group, row))
                     .set_value(group);
-                let r_expense = sheet.get_value(&(String::from("L") + &r.to_string()));
+                let r_expense = sheet.get_value(&cell(&columns.income_expense, r));
                 sheet
-                    .get_cell_mut(&(String::from("L") + &row.to_string()))
+                    .get_cell_mut(&cell(&columns.income_expense, row))
                     .set_value(r_expense);
+                changed = true;
             };
         }
     }
-    let _ = writer::xlsx::write(&book, path);
-    Ok(())
+    if !changed {
+        return Ok(());
+    }
+    before_save()?;
+    Ok(file::lib::save_file(&book, path)?)
 }
 
-pub fn re_group(path: &str, categories: &reading::Categories) -> Result<(), Box<dyn error::Error>> {
-    let mut book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name_mut("Kontoutskrift")
-        .map_err(|e| format!("could not open worksheet 'Kontoutskrift': {:?}", e))?;
-    // start on row 2
-    let mut row = 1;
+pub fn re_group(
+    path: &str,
+    schema: &Schema,
+    categories: &reading::Categories,
+    before_save: BeforeSave,
+) -> Result<(), Box<dyn error::Error>> {
+    schema.validate()?;
+    let mut book = file::lib::open_file(path)?;
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    let columns = &schema.columns;
+    let mut changed = false;
+    // start on the row after the header
+    let mut row = schema.header_row;
     loop {
         row += 1;
         // check if it is valid (accounting date is not empty), if empty, it is finished and break
-        if sheet.get_value(&(String::from("A") + &row.to_string())) == "" {
+        if sheet.get_value(&cell(&columns.accounting_date, row)) == "" {
             break;
         };
 
         // if the row is modified (change group manually) it should either have a bold group text
-        // or a text in the rightmost column (column 'O')
-        if sheet.get_value(&(String::from("O") + &row.to_string())) != "" {
+        // or a text in the note column
+        if sheet.get_value(&cell(&columns.note, row)) != "" {
             break;
         };
 
-        let group = sheet.get_value(&(String::from("K") + &row.to_string()));
+        let group = sheet.get_value(&cell(&columns.group, row));
         if group != "" {
-            let is_bold = match sheet
-                .get_style(&(String::from("K") + &row.to_string()))
-                .get_font()
-            {
+            let is_bold = match sheet.get_style(&cell(&columns.group, row)).get_font() {
                 Some(font) => font.get_bold(),
                 _ => &false, // this should not be an error if the text is bold...
             };
@@ -244,73 +472,208 @@ pub fn re_group(path: &str, categories: &reading::Categories) -> Result<(), Box<
             }
         };
 
-        let types = sheet.get_value(&(String::from("E") + &row.to_string()));
-        let text = sheet.get_value(&(String::from("F") + &row.to_string()));
-
-        let cat = lib::get_category(&text, &types, &categories);
+        let cat = lib::categorize(&row_transaction(sheet, schema, row), &categories);
         // if it does not have a category, skip this
         if cat.0 == "" || cat.1 == "" {
             continue;
         };
+        if cat.0 == group && cat.1 == sheet.get_value(&cell(&columns.income_expense, row)) {
+            continue;
+        }
 
+        changed = true;
         sheet
-            .get_cell_mut(&(String::from("K") + &row.to_string()))
+            .get_cell_mut(&cell(&columns.group, row))
             .set_value(cat.0);
         sheet
-            .get_cell_mut(&(String::from("L") + &row.to_string()))
+            .get_cell_mut(&cell(&columns.income_expense, row))
             .set_value(cat.1);
     }
-    let _ = writer::xlsx::write(&book, path);
-    Ok(())
+    if !changed {
+        return Ok(());
+    }
+    before_save()?;
+    Ok(file::lib::save_file(&book, path)?)
+}
+
+// A group the classifier guessed for a row without one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub row: u32,
+    pub transaction: Transaction,
+    pub group: String,
+    pub income_expense: String,
+    pub confidence: f64,
+}
+
+// The rows learn_groups wrote a group to, and the ones it was not sure enough about
+#[derive(Debug, Clone, Default)]
+pub struct Learned {
+    pub applied: Vec<Suggestion>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+// learns the groups from the rows that have one, and guesses the group of the rows the
+// categories do not give one, guesses at least as sure as the threshold (0 to 1) are written
+pub fn learn_groups(
+    path: &str,
+    schema: &Schema,
+    categories: &reading::Categories,
+    threshold: f64,
+    before_save: BeforeSave,
+) -> Result<Learned, Box<dyn error::Error>> {
+    schema.validate()?;
+    let mut book = file::lib::open_file(path)?;
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    let columns = &schema.columns;
+
+    let mut classifier = Classifier::new();
+    let mut unknown = vec![];
+    let mut row = schema.first_row();
+    // if the accounting date is empty, end of transactions
+    while !sheet
+        .get_value(&cell(&columns.accounting_date, row))
+        .is_empty()
+    {
+        let transaction = row_transaction(sheet, schema, row);
+        let group = sheet.get_value(&cell(&columns.group, row));
+        let income_expense = sheet.get_value(&cell(&columns.income_expense, row));
+        if group.is_empty() {
+            if lib::categorize(&transaction, categories).0.is_empty() {
+                unknown.push((row, transaction));
+            }
+        } else if !income_expense.is_empty() {
+            classifier.learn(&transaction, &group, &income_expense);
+        }
+        row += 1;
+    }
+
+    let mut learned = Learned::default();
+    for (row, transaction) in unknown {
+        let guess = match classifier.guess(&transaction) {
+            Some(guess) => guess,
+            None => continue,
+        };
+        let suggestion = Suggestion {
+            row,
+            transaction,
+            group: guess.group,
+            income_expense: guess.income_expense,
+            confidence: guess.confidence,
+        };
+        if suggestion.confidence >= threshold {
+            set_group(sheet, schema, &suggestion);
+            learned.applied.push(suggestion);
+        } else {
+            learned.suggestions.push(suggestion);
+        }
+    }
+    if !learned.applied.is_empty() {
+        before_save()?;
+        file::lib::save_file(&book, path)?;
+    }
+    Ok(learned)
+}
+
+// writes the accepted suggestions, a row that has got a group since it was suggested, or that
+// no longer has the text and date of the suggested transaction, e.g. because rows were sorted or
+// inserted, is left as it is
+pub fn apply_suggestions(
+    path: &str,
+    schema: &Schema,
+    suggestions: &[Suggestion],
+    before_save: BeforeSave,
+) -> Result<(), Box<dyn error::Error>> {
+    schema.validate()?;
+    let mut book = file::lib::open_file(path)?;
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    let mut changed = false;
+    for suggestion in suggestions {
+        let transaction = row_transaction(sheet, schema, suggestion.row);
+        if sheet
+            .get_value(&cell(&schema.columns.group, suggestion.row))
+            .is_empty()
+            && transaction.text == suggestion.transaction.text
+            && transaction.accounting_date == suggestion.transaction.accounting_date
+        {
+            set_group(sheet, schema, suggestion);
+            changed = true;
+        }
+    }
+    if !changed {
+        return Ok(());
+    }
+    before_save()?;
+    Ok(file::lib::save_file(&book, path)?)
+}
+
+fn set_group(sheet: &mut Worksheet, schema: &Schema, suggestion: &Suggestion) {
+    let columns = &schema.columns;
+    sheet
+        .get_cell_mut(&cell(&columns.group, suggestion.row))
+        .set_value(&suggestion.group);
+    sheet
+        .get_cell_mut(&cell(&columns.income_expense, suggestion.row))
+        .set_value(&suggestion.income_expense);
 }
 
 pub fn re_date(
     path: &str,
+    schema: &Schema,
     delimiter: &str,
     date_month_style: &str,
     date_language: &str,
     date_capitalize: &bool,
+    before_save: BeforeSave,
 ) -> Result<(), Box<dyn error::Error>> {
-    let mut book =
-        file::lib::open_file(path).map_err(|e| format!("could not open workbook: {:?}", e))?;
-    let sheet = book
-        .get_sheet_by_name_mut("Kontoutskrift")
-        .map_err(|e| format!("could not open worksheet 'Kontoutskrift': {:?}", e))?;
-    // start on row 2
-    let mut row = 1;
+    schema.validate()?;
+    let mut book = file::lib::open_file(path)?;
+    let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+    let columns = &schema.columns;
+    let mut changed = false;
+    // start on the row after the header
+    let mut row = schema.header_row;
     loop {
         row += 1;
         // check if it is valid (accounting date is not empty), if empty, it is finished and break
-        if sheet.get_value(&(String::from("A") + &row.to_string())) == "" {
+        if sheet.get_value(&cell(&columns.accounting_date, row)) == "" {
             break;
         };
 
-        let accounting_date = sheet.get_value(&(String::from("A") + &row.to_string()));
-        let cur_del = lib::get_delimiter(&accounting_date);
-        let interest_date = sheet.get_value(&(String::from("B") + &row.to_string()));
-        sheet
-            .get_cell_mut(&(String::from("A") + &row.to_string()))
-            .set_value(accounting_date.replace(&cur_del, delimiter));
-        sheet
-            .get_cell_mut(&(String::from("B") + &row.to_string()))
-            .set_value(interest_date.replace(&cur_del, delimiter));
+        // older rows have the dates as text, they are made excel dates
+        let accounting_date =
+            lib::cell_to_date(&sheet.get_value(&cell(&columns.accounting_date, row)));
+        let interest_date = lib::cell_to_date(&sheet.get_value(&cell(&columns.interest_date, row)));
+        if let Ok(interest_date) = interest_date {
+            changed |= set_date(
+                sheet,
+                &cell(&columns.interest_date, row),
+                interest_date,
+                delimiter,
+            );
+        }
 
-        match lib::string_to_date(&accounting_date, &lib::get_delimiter(&accounting_date)) {
-            Ok(date) => {
+        if let Ok(date) = accounting_date {
+            changed |= set_date(sheet, &cell(&columns.accounting_date, row), date, delimiter);
+            let month = lib::get_month(
+                date.month(),
+                date_month_style,
+                date_language,
+                date_capitalize,
+            );
+            if sheet.get_value(&cell(&columns.month, row)) != month {
                 sheet
-                    .get_cell_mut(&(String::from("N") + &row.to_string()))
-                    .set_value(lib::get_month(
-                        date.month(),
-                        date_month_style,
-                        date_language,
-                        date_capitalize,
-                    ));
+                    .get_cell_mut(&cell(&columns.month, row))
+                    .set_value(month);
+                changed = true;
             }
-            Err(_) => (),
-        };
+        }
+    }
+    if !changed {
+        return Ok(());
     }
-    let _ = writer::xlsx::write(&book, path);
-    Ok(())
+    before_save()?;
+    Ok(file::lib::save_file(&book, path)?)
 }
 
 #[cfg(test)]
@@ -326,11 +689,286 @@ mod writing_tests {
             &mock_transaction.path,
             mock_transaction.info,
             &mock_transaction.categories,
-            &mock_transaction.date_delimiter,
-            &mock_transaction.date_month_style,
-            &mock_transaction.date_language,
-            &mock_transaction.date_capitalize,
+            &WriteOptions {
+                schema: &Schema::default(),
+                rates: &mock_transaction.rates,
+                date_delimiter: &mock_transaction.date_delimiter,
+                date_month_style: &mock_transaction.date_month_style,
+                date_language: &mock_transaction.date_language,
+                date_capitalize: mock_transaction.date_capitalize,
+            },
+            &mut || Ok(()),
         )?;
         Ok(())
     }
+
+    #[test]
+    fn test_write_dates_and_amounts() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_dates.xlsx";
+        std::fs::copy("src/templates/template.xlsx", path)?;
+        // a row from an older version, with the date and amounts as text
+        let mut book = file::lib::open_file(path)?;
+        let sheet = book.get_sheet_by_name_mut("Kontoutskrift")?;
+        sheet.get_cell_mut("A2").set_value_from_string("01.06.2022");
+        sheet.get_cell_mut("B2").set_value_from_string("01.06.2022");
+        sheet.get_cell_mut("G2").set_value_from_string("12.50");
+        sheet.get_cell_mut("H2").set_value_from_string("0.00");
+        file::lib::save_file(&book, path)?;
+
+        let date = chrono::NaiveDate::from_ymd(2022, 6, 3);
+        let result = write(
+            path,
+            Transactions::from(vec![Transaction {
+                original_amount: Money::from_ore(-125),
+                original_currency: String::from("EUR"),
+                ..Transaction::new(date, Money::from_ore(-1250))
+            }]),
+            &reading::Categories::default(),
+            &WriteOptions {
+                schema: &Schema::default(),
+                rates: &ExchangeRates::default(),
+                date_delimiter: "/",
+                date_month_style: "short",
+                date_language: "english",
+                date_capitalize: false,
+            },
+            &mut || Ok(()),
+        )
+        .and_then(|_| {
+            let transactions = reading::get_workbook_transactions(path, &Schema::default())?;
+            let book = file::lib::open_file(path)?;
+            let sheet = book.get_sheet_by_name("Kontoutskrift")?;
+            Ok((
+                transactions,
+                sheet.get_value("A3"),
+                sheet.get_style("A3").get_number_format().clone(),
+                sheet.get_value("G3"),
+                sheet.get_value("Q3"),
+                sheet.get_value("Q1"),
+            ))
+        });
+        std::fs::remove_file(path)?;
+        let (transactions, date_value, date_format, amount_value, original, header) = result?;
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(
+            transactions.column(|t| t.accounting_date),
+            vec![chrono::NaiveDate::from_ymd(2022, 6, 1), date]
+        );
+        assert_eq!(transactions[1].out_of_account, Money::from_ore(1250));
+        assert_eq!(date_value, "44715");
+        assert_eq!(date_format.unwrap().get_format_code(), "dd/mm/yyyy");
+        assert_eq!(amount_value, "12.5");
+        assert_eq!(original, "-1.25");
+        assert_eq!(header, "ORIGINALBELØP");
+        Ok(())
+    }
+
+    #[test]
+    fn test_prepare_import_checks_balances() -> Result<(), Box<dyn error::Error>> {
+        let workbook_path = "test_balances.xlsx";
+        let statement_path = "test_balances.sta";
+        std::fs::copy("src/templates/template.xlsx", workbook_path)?;
+        std::fs::write(
+            statement_path,
+            ":20:STARTUMS\n:25:12345678/0001234567\n:60F:C220531NOK1000,00\n\
+             :61:2206030602D112,50NTRFNONREF//BANK1\n:86:Kiwi AS\n\
+             :61:2206100610C500,NMSCREF2\n:86:Salary\n:62F:C220630NOK1387,50\n",
+        )?;
+        let account = accounting::lib::Account {
+            name: String::from("Brukskonto"),
+            number: 12345678903,
+            currency: String::from("NOK"),
+        };
+        // a row of the account in the period that the bank does not know about
+        let cash = Transaction {
+            account: account.name.clone(),
+            text: String::from("Cash"),
+            ..Transaction::new(
+                chrono::NaiveDate::from_ymd(2022, 6, 15),
+                Money::from_kroner(-20),
+            )
+        };
+        let result = write(
+            workbook_path,
+            Transactions::from(vec![cash]),
+            &reading::Categories::default(),
+            &WriteOptions {
+                schema: &Schema::default(),
+                rates: &ExchangeRates::default(),
+                date_delimiter: ".",
+                date_month_style: "short",
+                date_language: "english",
+                date_capitalize: false,
+            },
+            &mut || Ok(()),
+        )
+        .and_then(|_| {
+            prepare_import(
+                workbook_path,
+                statement_path,
+                &accounting::mt940::Mt940,
+                &account,
+                &Schema::default(),
+                3,
+                false,
+            )
+        });
+        std::fs::remove_file(workbook_path)?;
+        std::fs::remove_file(statement_path)?;
+        let pending = result?;
+
+        // the statement adds up, but the workbook has 20 more going out after the import
+        let checks = pending.balance_checks();
+        assert_eq!(checks.len(), 2);
+        assert!(checks[0].1.is_ok());
+        assert_eq!(checks[1].1.expected, Money::from_ore(38750));
+        assert_eq!(checks[1].1.found, Money::from_ore(36750));
+        assert_eq!(
+            checks[1].1.to_string(),
+            "the balances say 387.50 but the transactions add up to 367.50"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pending_import_accepted() {
+        let date = chrono::NaiveDate::from_ymd(2022, 6, 1);
+        let transactions: Transactions = (1..=3)
+            .map(|i| Transaction::new(date, Money::from_kroner(i)))
+            .collect();
+        let suspect = |index: usize| lib::Suspect {
+            index,
+            existing: transactions[index].clone(),
+            similarity: 1.0,
+        };
+        let pending = PendingImport {
+            suspects: vec![suspect(0), suspect(2)],
+            transactions: transactions.clone(),
+            duplicates: transactions.slice(0..1),
+            ..PendingImport::default()
+        };
+        assert_eq!(pending.accepted(&[true, true]), transactions);
+        assert_eq!(pending.skipped(&[true, true]).len(), 1);
+        let accepted = pending.accepted(&[false, true]);
+        assert_eq!(accepted.len(), 2);
+        assert_eq!(accepted[0], transactions[1]);
+        assert_eq!(pending.skipped(&[false, true]).len(), 2);
+    }
+
+    #[test]
+    fn test_preview() {
+        let date = chrono::NaiveDate::from_ymd(2022, 6, 1);
+        let mut kiwi = Transaction::new(date, Money::from_kroner(-45));
+        kiwi.text = String::from("KIWI 123");
+        let mut qif = Transaction::new(date, Money::from_kroner(-20));
+        qif.category = String::from("Car");
+        let categories = reading::Categories::new(
+            vec![
+                vec![
+                    String::from("Food"),
+                    String::from("Expense"),
+                    String::from("kiwi"),
+                ],
+                vec![String::from("Car"), String::from("Expense")],
+            ],
+            vec![],
+        );
+        let rows = preview(&Transactions::from(vec![kiwi.clone(), qif]), &categories);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].transaction, kiwi);
+        assert_eq!(rows[0].group, "Food");
+        assert_eq!(rows[1].group, "Car");
+        assert_eq!(rows[1].income_expense, "Expense");
+    }
+
+    #[test]
+    fn test_learn_groups() -> Result<(), Box<dyn error::Error>> {
+        let path = "test_learn.xlsx";
+        std::fs::copy("src/templates/template.xlsx", path)?;
+        let schema = Schema::default();
+        let mut book = file::lib::open_file(path)?;
+        let sheet = reading::open_sheet_mut(&mut book, path, &schema.transaction_sheet)?;
+        for (i, (text, group)) in [
+            ("KIWI BISLETT", "Food"),
+            ("KIWI MAJORSTUEN", "Food"),
+            ("KIWI STORO", "Food"),
+            ("REMA TORSHOV", "Food"),
+            ("RUTER BILLETT", "Transport"),
+            ("RUTER APP", "Transport"),
+            ("KIWI GRUNERLOKKA", ""),
+            ("KIWI RUTER", ""),
+            ("ESSO STORO", ""),
+            ("VINMONOPOLET", ""),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let row = schema.first_row() + i as u32;
+            let columns = &schema.columns;
+            for (column, value) in [
+                (&columns.accounting_date, "44715"),
+                (&columns.interest_date, "44715"),
+                (&columns.text, *text),
+                (&columns.out_of_account, "50"),
+                (&columns.into_account, "0"),
+                (&columns.group, *group),
+            ] {
+                sheet.get_cell_mut(&cell(column, row)).set_value(value);
+            }
+            if !group.is_empty() {
+                sheet
+                    .get_cell_mut(&cell(&columns.income_expense, row))
+                    .set_value("Expense");
+            }
+        }
+        file::lib::save_file(&book, path)?;
+
+        // the categories give the esso row a group, so it is not guessed
+        let categories = reading::Categories::new(
+            vec![vec![
+                String::from("Car"),
+                String::from("Expense"),
+                String::from("esso"),
+            ]],
+            vec![],
+        );
+        // each call that changes the workbook saves it once, applying the same groups again
+        // changes nothing
+        let mut saves = 0;
+        let mut count_save = || -> Result<(), Box<dyn error::Error>> {
+            saves += 1;
+            Ok(())
+        };
+        let result =
+            learn_groups(path, &schema, &categories, 0.8, &mut count_save).and_then(|learned| {
+                // a suggestion for a row that now has another transaction is not written
+                let mut moved = learned.suggestions.clone();
+                moved[0].transaction.text = String::from("REMA");
+                apply_suggestions(path, &schema, &moved, &mut count_save)?;
+                let mut moved = learned.suggestions.clone();
+                moved[0].transaction.accounting_date = NaiveDate::from_ymd(2022, 6, 4);
+                apply_suggestions(path, &schema, &moved, &mut count_save)?;
+                apply_suggestions(path, &schema, &learned.suggestions, &mut count_save)?;
+                apply_suggestions(path, &schema, &learned.suggestions, &mut count_save)?;
+                let transactions = reading::get_workbook_transactions(path, &schema)?;
+                Ok((learned, transactions))
+            });
+        std::fs::remove_file(path)?;
+        let (learned, transactions) = result?;
+        assert_eq!(saves, 2);
+
+        let rows = |suggestions: &[Suggestion]| -> Vec<u32> {
+            suggestions.iter().map(|s| s.row).collect()
+        };
+        assert_eq!(rows(&learned.applied), vec![8]);
+        assert_eq!(learned.applied[0].group, "Food");
+        assert_eq!(rows(&learned.suggestions), vec![9]);
+        assert!(learned.suggestions[0].confidence < 0.8);
+        assert_eq!(
+            transactions.column(|t| t.category.clone())[6..],
+            ["Food", &learned.suggestions[0].group, "", ""]
+        );
+        Ok(())
+    }
 }
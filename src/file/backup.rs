@@ -0,0 +1,141 @@
+use chrono::Local;
+use std::error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the folder next to the workbook where the backups are kept
+pub const BACKUP_DIR: &str = "backups";
+// how many backups of a workbook are kept by default
+pub const DEFAULT_KEEP: usize = 10;
+
+// sorts in the order the backups were made
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S%3f";
+
+// copies the workbook to the backup folder as <name>.<timestamp>.xlsx and removes
+// the oldest backups of the workbook so at most keep are left, returns the path of the copy
+pub fn backup(path: &str, keep: usize) -> Result<PathBuf, Box<dyn error::Error>> {
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir).map_err(|e| format!("could not create backup folder: {:?}", e))?;
+
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let mut backup_path = dir.join(backup_name(path, &timestamp)?);
+    // two backups in the same millisecond get a number after the same timestamp, so they
+    // still sort before a backup made in the next millisecond
+    let mut i = 1;
+    while backup_path.exists() {
+        backup_path = dir.join(backup_name(path, &format!("{}{}", timestamp, i))?);
+        i += 1;
+    }
+    fs::copy(path, &backup_path).map_err(|e| format!("could not back up workbook: {:?}", e))?;
+
+    let backups = backups(path)?;
+    if backups.len() > keep {
+        for old in &backups[..backups.len() - keep] {
+            fs::remove_file(old).map_err(|e| format!("could not remove old backup: {:?}", e))?;
+        }
+    }
+    Ok(backup_path)
+}
+
+// the backups of the workbook, oldest first
+pub fn backups(path: &str) -> Result<Vec<PathBuf>, Box<dyn error::Error>> {
+    let dir = backup_dir(path);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let prefix = format!("{}.", stem(path)?);
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir)
+        .map_err(|e| format!("could not read backup folder: {:?}", e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|backup| {
+            let name = backup
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            // the timestamp tells it apart from the backups of e.g. <name>.old.xlsx
+            match name
+                .strip_prefix(&prefix)
+                .and_then(|n| n.strip_suffix(".xlsx"))
+            {
+                Some(timestamp) => {
+                    timestamp.len() >= 18
+                        && timestamp.chars().all(|c| c.is_ascii_digit() || c == '-')
+                }
+                None => false,
+            }
+        })
+        .collect();
+    backups.sort();
+    Ok(backups)
+}
+
+// puts the latest backup back in place of the workbook and removes it, so the next
+// undo goes one more step back, returns the path of the backup that was restored
+pub fn restore_latest(path: &str) -> Result<PathBuf, Box<dyn error::Error>> {
+    let latest = backups(path)?
+        .pop()
+        .ok_or_else(|| format!("there is no backup of {}", path))?;
+    fs::copy(&latest, path).map_err(|e| format!("could not restore backup: {:?}", e))?;
+    fs::remove_file(&latest).map_err(|e| format!("could not remove restored backup: {:?}", e))?;
+    Ok(latest)
+}
+
+fn backup_dir(path: &str) -> PathBuf {
+    Path::new(path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(BACKUP_DIR)
+}
+
+fn stem(path: &str) -> Result<String, Box<dyn error::Error>> {
+    Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .map(String::from)
+        .ok_or_else(|| format!("{} is not a file", path).into())
+}
+
+fn backup_name(path: &str, timestamp: &str) -> Result<String, Box<dyn error::Error>> {
+    Ok(format!("{}.{}.xlsx", stem(path)?, timestamp))
+}
+
+#[cfg(test)]
+mod test_backup {
+    use super::*;
+
+    #[test]
+    fn test_backup_and_restore() -> Result<(), Box<dyn error::Error>> {
+        let dir = std::env::temp_dir().join("rust_accounting_test_backup");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let workbook = dir.join("workbook.xlsx");
+        let path = workbook.to_str().unwrap();
+        // another workbook in the same folder
+        fs::write(dir.join("workbook.old.xlsx"), "other")?;
+        backup(dir.join("workbook.old.xlsx").to_str().unwrap(), 3)?;
+
+        for version in 1..=4 {
+            fs::write(&workbook, version.to_string())?;
+            backup(path, 3)?;
+        }
+        fs::write(&workbook, "5")?;
+        assert_eq!(backups(path)?.len(), 3);
+
+        restore_latest(path)?;
+        assert_eq!(fs::read_to_string(&workbook)?, "4");
+        restore_latest(path)?;
+        assert_eq!(fs::read_to_string(&workbook)?, "3");
+        restore_latest(path)?;
+        assert_eq!(fs::read_to_string(&workbook)?, "2");
+        // the oldest was removed
+        assert!(restore_latest(path).is_err());
+        assert_eq!(
+            backups(dir.join("workbook.old.xlsx").to_str().unwrap())?.len(),
+            1
+        );
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
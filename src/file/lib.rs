@@ -1,9 +1,127 @@
 use std::error;
+use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 use umya_spreadsheet::*;
 
-pub fn open_file(path: &str) -> Result<umya_spreadsheet::Spreadsheet, Box<dyn error::Error>> {
-    Ok(reader::xlsx::read(path).map_err(|e| format!("could not open file: {:?}", e))?)
+use crate::error::Error;
+
+pub fn open_file(path: &str) -> Result<umya_spreadsheet::Spreadsheet, Error> {
+    reader::xlsx::read(path).map_err(|e| Error::Io {
+        file: String::from(path),
+        reason: format!("{:?}", e),
+    })
+}
+
+// writes the workbook to a temporary file next to it and renames it over the workbook when
+// everything is on disk, so a failed or interrupted save leaves the old workbook as it was
+pub fn save_file(book: &umya_spreadsheet::Spreadsheet, path: &str) -> Result<(), Error> {
+    let write_error = |reason: String| Error::Write {
+        file: String::from(path),
+        reason,
+    };
+    let target = Path::new(path);
+    let name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| write_error(String::from("it is not a file")))?;
+    let tmp_path = target.with_file_name(format!(".{}.tmp", name));
+
+    let result = write_tmp_file(book, &tmp_path).and_then(|_| {
+        fs::rename(&tmp_path, target).map_err(|e| format!("could not replace it: {:?}", e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result.map_err(write_error)
+}
+
+// the error is the reason, save_file adds the workbook
+fn write_tmp_file(book: &umya_spreadsheet::Spreadsheet, tmp_path: &Path) -> Result<(), String> {
+    let file = fs::File::create(tmp_path)
+        .map_err(|e| format!("could not create temporary workbook: {:?}", e))?;
+    let mut writer = BufWriter::new(file);
+    writer::xlsx::write_writer(book, &mut writer)
+        .map_err(|e| format!("could not write workbook: {:?}", e))?;
+    writer
+        .flush()
+        .map_err(|e| format!("could not write workbook: {:?}", e))?;
+    writer
+        .get_ref()
+        .sync_all()
+        .map_err(|e| format!("could not write workbook: {:?}", e))?;
+    Ok(())
+}
+
+// rows of fields, each with the line of the file it starts on
+pub type CsvRows = Vec<(usize, Vec<String>)>;
+
+// reads a delimiter separated file into rows of fields, quoted fields may contain the delimiter
+pub fn read_csv(path: &str, delimiter: char) -> Result<CsvRows, Error> {
+    let bytes = fs::read(path).map_err(|e| Error::Io {
+        file: String::from(path),
+        reason: format!("{:?}", e),
+    })?;
+    // bank exports are either utf-8 or latin-1
+    let content = match String::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(e) => e.into_bytes().iter().map(|b| *b as char).collect(),
+    };
+    Ok(parse_csv(content.trim_start_matches('\u{feff}'), delimiter))
+}
+
+// empty lines are skipped and quoted fields may span several lines,
+// so the line of a row is counted rather than taken from its index
+pub fn parse_csv(content: &str, delimiter: char) -> CsvRows {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                '\n' => {
+                    field.push(c);
+                    line += 1;
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            '\r' => (),
+            '\n' => {
+                row.push(field.trim().to_string());
+                field = String::new();
+                // skip empty lines
+                if !(row.len() == 1 && row[0].is_empty()) {
+                    rows.push((start, row));
+                }
+                row = vec![];
+                line += 1;
+                start = line;
+            }
+            _ if c == delimiter => {
+                row.push(field.trim().to_string());
+                field = String::new();
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field.trim().to_string());
+        rows.push((start, row));
+    }
+    rows
 }
 
 pub fn valid_file(path: &str) -> Result<String, Box<dyn error::Error>> {
@@ -29,4 +147,54 @@ mod test_file {
     fn test_open_file() {
         assert!(open_file("test").is_err());
     }
+
+    #[test]
+    fn test_save_file() -> Result<(), Box<dyn error::Error>> {
+        let dir = std::env::temp_dir().join("rust_accounting_test_save_file");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("workbook.xlsx");
+        let path = path.to_str().unwrap();
+
+        let mut book = umya_spreadsheet::new_file();
+        book.get_sheet_mut(&0)?
+            .get_cell_mut("A1")
+            .set_value("saved");
+        save_file(&book, path)?;
+        assert_eq!(open_file(path)?.get_sheet(&0)?.get_value("A1"), "saved");
+        // only the workbook is left
+        assert_eq!(fs::read_dir(&dir)?.count(), 1);
+
+        // a folder that does not exist reports the error and keeps nothing
+        assert!(save_file(
+            &book,
+            dir.join("missing").join("workbook.xlsx").to_str().unwrap()
+        )
+        .is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let rows = parse_csv(
+            "a;b;c\r\n1;\"2;3\";\"say \"\"hi\"\"\"\n\n4;\"5\n5\";6\n7;8;9",
+            ';',
+        );
+        let rows: Vec<(usize, Vec<&str>)> = rows
+            .iter()
+            .map(|(line, row)| (*line, row.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, vec!["a", "b", "c"]),
+                (2, vec!["1", "2;3", "say \"hi\""]),
+                (4, vec!["4", "5\n5", "6"]),
+                (6, vec!["7", "8", "9"]),
+            ]
+        );
+        assert!(read_csv("test", ';').is_err());
+    }
 }
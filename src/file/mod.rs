@@ -1 +1,2 @@
-pub mod lib;
\ No newline at end of file
+pub mod backup;
+pub mod lib;
@@ -1,4 +1,5 @@
 pub mod accounting;
+pub mod error;
 pub mod excel;
 pub mod file;
 pub mod settings;
\ No newline at end of file
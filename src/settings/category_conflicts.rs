@@ -0,0 +1,97 @@
+use eframe::egui;
+use egui::{Color32, RichText};
+
+use crate::excel::conflicts::{Conflict, Shadow};
+
+// The transactions of the workbook more than one group has a rule for, and the rules that
+// are never used because another rule takes their transactions
+#[derive(Debug, Default)]
+pub struct CategoryConflicts {
+    pub conflicts: Vec<Conflict>,
+    pub shadows: Vec<Shadow>,
+    // the workbook row of the first transaction
+    pub first_row: u32,
+    // false until the workbook has been checked
+    pub checked: bool,
+    // set when the user wants to check the workbook again
+    pub recheck: bool,
+}
+
+impl CategoryConflicts {
+    pub fn init(&mut self, conflicts: Vec<Conflict>, shadows: Vec<Shadow>, first_row: u32) {
+        self.conflicts = conflicts;
+        self.shadows = shadows;
+        self.first_row = first_row;
+        self.checked = true;
+        self.recheck = false;
+    }
+}
+
+impl super::Window for CategoryConflicts {
+    fn name(&self) -> &'static str {
+        "Category Conflicts"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .collapsible(true)
+            .resizable(true)
+            .open(open)
+            .hscroll(true)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for CategoryConflicts {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if !self.checked {
+            ui.label("The categories have not been checked");
+        } else {
+            ui.heading(format!("Shadowed rules: {}", self.shadows.len()));
+            for shadow in &self.shadows {
+                ui.label(
+                    RichText::new(format!("{} is never used", shadow.rule))
+                        .color(Color32::from_rgb(255, 50, 50)),
+                );
+                ui.label(format!("    {} takes its transactions", shadow.by));
+            }
+            ui.add_space(8.0);
+
+            ui.heading(format!(
+                "Transactions in more than one group: {}",
+                self.conflicts.len()
+            ));
+            egui::Grid::new("category conflicts")
+                .striped(true)
+                .show(ui, |ui| {
+                    for column in ["row", "date", "text", "amount", "chosen", "also"] {
+                        ui.label(RichText::new(column).strong());
+                    }
+                    ui.end_row();
+                    for conflict in &self.conflicts {
+                        ui.label((self.first_row as usize + conflict.index).to_string());
+                        ui.label(conflict.transaction.accounting_date.to_string());
+                        ui.label(&conflict.transaction.text);
+                        ui.label(conflict.transaction.amount().to_string());
+                        ui.label(&conflict.chosen);
+                        let others: Vec<String> = conflict
+                            .rules
+                            .iter()
+                            .filter(|rule| rule.group != conflict.chosen)
+                            .map(|rule| rule.to_string())
+                            .collect();
+                        ui.label(others.join("\n"));
+                        ui.end_row();
+                    }
+                });
+        }
+        ui.add_space(8.0);
+        if ui.button("check again").clicked() {
+            self.recheck = true;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use eframe::egui;
+use egui::RichText;
+
+use crate::excel::writing::Suggestion;
+
+// Lists the groups learned from the workbook that were not sure enough to be written, so each
+// can be accepted before it is written
+#[derive(Debug, Default)]
+pub struct CategorySuggestions {
+    pub suggestions: Vec<Suggestion>,
+    // one per suggestion, true if the group should be written
+    pub accepted: Vec<bool>,
+    // set when the user wants to write the accepted groups
+    pub confirmed: bool,
+}
+
+impl CategorySuggestions {
+    // accepts no suggestion until the user does
+    pub fn init(&mut self, suggestions: Vec<Suggestion>) {
+        self.accepted = vec![false; suggestions.len()];
+        self.suggestions = suggestions;
+        self.confirmed = false;
+    }
+
+    pub fn reset(&mut self) {
+        *self = CategorySuggestions::default();
+    }
+
+    // the suggestions to write
+    pub fn accepted(&self) -> Vec<Suggestion> {
+        self.suggestions
+            .iter()
+            .zip(self.accepted.iter())
+            .filter(|(_, accepted)| **accepted)
+            .map(|(suggestion, _)| suggestion.clone())
+            .collect()
+    }
+}
+
+impl super::Window for CategorySuggestions {
+    fn name(&self) -> &'static str {
+        "Category Suggestions"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .collapsible(true)
+            .resizable(true)
+            .open(open)
+            .hscroll(true)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for CategorySuggestions {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if self.suggestions.is_empty() {
+            ui.label("Nothing to suggest");
+            return;
+        }
+        ui.label(format!(
+            "{} rows without a group look like rows with one.",
+            self.suggestions.len()
+        ));
+        ui.label("Only the checked groups are written.");
+        ui.add_space(8.0);
+
+        egui::Grid::new("category suggestions")
+            .striped(true)
+            .show(ui, |ui| {
+                for column in ["write", "row", "date", "text", "amount", "group", "sure"] {
+                    ui.label(RichText::new(column).strong());
+                }
+                ui.end_row();
+                for (suggestion, accepted) in self.suggestions.iter().zip(self.accepted.iter_mut())
+                {
+                    ui.checkbox(accepted, "");
+                    ui.label(suggestion.row.to_string());
+                    ui.label(suggestion.transaction.accounting_date.to_string());
+                    ui.label(&suggestion.transaction.text);
+                    ui.label(suggestion.transaction.amount().to_string());
+                    ui.label(&suggestion.group);
+                    ui.label(format!("{:.0} %", suggestion.confidence * 100.0));
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("write").clicked() {
+                self.confirmed = true;
+            }
+            if ui.button("cancel").clicked() {
+                self.reset();
+            }
+        });
+    }
+}
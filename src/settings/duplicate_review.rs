@@ -0,0 +1,105 @@
+use eframe::egui;
+use egui::{Color32, RichText};
+
+use crate::excel::writing::PendingImport;
+
+// Lists the probable duplicates of an import, so each can be accepted or rejected before it is written
+#[derive(Debug, Default)]
+pub struct DuplicateReview {
+    pub pending: Option<PendingImport>,
+    // one per suspect, true if the transaction should be written anyway
+    pub accepted: Vec<bool>,
+    // set when the user wants to write the accepted transactions
+    pub confirmed: bool,
+}
+
+impl DuplicateReview {
+    // rejects every suspect until the user accepts it
+    pub fn init(&mut self, pending: PendingImport) {
+        self.accepted = vec![false; pending.suspects.len()];
+        self.pending = Some(pending);
+        self.confirmed = false;
+    }
+
+    pub fn reset(&mut self) {
+        *self = DuplicateReview::default();
+    }
+}
+
+impl super::Window for DuplicateReview {
+    fn name(&self) -> &'static str {
+        "Duplicate Review"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .collapsible(true)
+            .resizable(true)
+            .open(open)
+            .hscroll(true)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for DuplicateReview {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let pending = match &self.pending {
+            Some(pending) => pending,
+            None => {
+                ui.label("Nothing to review");
+                return;
+            }
+        };
+        ui.label(format!(
+            "{} of {} new transactions look like transactions already in the workbook.",
+            pending.suspects.len(),
+            pending.transactions.len()
+        ));
+        ui.label("Only the checked transactions are inserted.");
+        ui.add_space(8.0);
+
+        egui::Grid::new("duplicate review")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("insert").strong());
+                ui.label("");
+                ui.label(RichText::new("date").strong());
+                ui.label(RichText::new("text").strong());
+                ui.label(RichText::new("amount").strong());
+                ui.label(RichText::new("similarity").strong());
+                ui.end_row();
+                for (suspect, accepted) in pending.suspects.iter().zip(self.accepted.iter_mut()) {
+                    let new = &pending.transactions[suspect.index];
+                    ui.checkbox(accepted, "");
+                    ui.label(RichText::new("new").color(Color32::from_rgb(100, 100, 255)));
+                    ui.label(new.accounting_date.to_string());
+                    ui.label(&new.text);
+                    ui.label(new.amount().to_string());
+                    ui.label(format!("{:.0} %", suspect.similarity * 100.0));
+                    ui.end_row();
+
+                    ui.label("");
+                    ui.label(RichText::new("workbook").color(Color32::from_rgb(200, 50, 255)));
+                    ui.label(suspect.existing.accounting_date.to_string());
+                    ui.label(&suspect.existing.text);
+                    ui.label(suspect.existing.amount().to_string());
+                    ui.label("");
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("write").clicked() {
+                self.confirmed = true;
+            }
+            if ui.button("cancel").clicked() {
+                self.reset();
+            }
+        });
+    }
+}
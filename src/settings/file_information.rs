@@ -1,13 +1,20 @@
 use eframe::egui;
+use egui::{Color32, RichText};
+
+use crate::excel::schema::{Schema, SCHEMA_FILE};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[cfg_attr(feature = "serde", serde(default))]
-
 pub struct FileInformation {
     pub workbook_file: String,
     pub transaction_file: String,
-    pub sheet_names: Vec<String>,
+    // historical exchange rates, date;currency;rate
+    pub exchange_rate_file: String,
+    // the layout of the workbook, read from the schema file
+    pub schema: Schema,
+    // the result of the last load or save of the schema
+    schema_status: Result<String, String>,
 }
 
 impl super::Window for FileInformation {
@@ -33,26 +40,95 @@ impl super::View for FileInformation {
             ui.heading("Sheet names: ");
         });
         egui::Grid::new("parent grid").striped(true).show(ui, |ui| {
-            for sheet_name in &self.sheet_names {
-                ui.horizontal(|ui| {
-                    ui.label(format!("'{}'", sheet_name));
+            ui.label("transactions");
+            ui.text_edit_singleline(&mut self.schema.transaction_sheet);
+            ui.end_row();
+            ui.label("categories");
+            ui.text_edit_singleline(&mut self.schema.category_sheet);
+            ui.end_row();
+            ui.label("accounts");
+            ui.text_edit_singleline(&mut self.schema.account_sheet);
+            ui.end_row();
+            ui.label("header row");
+            ui.add(egui::DragValue::new(&mut self.schema.header_row).clamp_range(1..=100));
+            ui.end_row();
+        });
+
+        ui.collapsing("transaction columns", |ui| {
+            egui::Grid::new("transaction columns")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, column) in self.schema.columns.fields_mut() {
+                        ui.label(name);
+                        ui.add(egui::TextEdit::singleline(column).desired_width(40.0));
+                        ui.end_row();
+                    }
+                });
+        });
+        ui.collapsing("account columns", |ui| {
+            egui::Grid::new("account columns")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, column) in self.schema.account_columns.fields_mut() {
+                        ui.label(name);
+                        ui.add(egui::TextEdit::singleline(column).desired_width(40.0));
+                        ui.end_row();
+                    }
                 });
-                ui.end_row();
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("save schema").clicked() {
+                self.schema_status = self
+                    .schema
+                    .save(SCHEMA_FILE)
+                    .map(|_| format!("Saved to {}", SCHEMA_FILE))
+                    .map_err(|e| e.to_string());
+            }
+            if ui.button("reload schema").clicked() {
+                self.schema_status = match Schema::load(SCHEMA_FILE) {
+                    Ok(schema) => {
+                        self.schema = schema;
+                        Ok(format!("Loaded {}", SCHEMA_FILE))
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+            }
+            if ui.button("default schema").clicked() {
+                self.schema = Schema::default();
+                self.schema_status = Ok(String::new());
             }
         });
+        // an error in the schema is shown before the status of the last save
+        match self.schema.validate() {
+            Err(e) => {
+                ui.label(RichText::new(e.to_string()).color(Color32::from_rgb(255, 50, 50)));
+            }
+            Ok(_) => match &self.schema_status {
+                Ok(status) => {
+                    ui.label(RichText::new(status).color(Color32::from_rgb(50, 255, 50)));
+                }
+                Err(e) => {
+                    ui.label(RichText::new(e).color(Color32::from_rgb(255, 50, 50)));
+                }
+            },
+        };
     }
 }
 
 impl Default for FileInformation {
     fn default() -> Self {
+        // an invalid schema file is reported in the window, the default schema is used until it is fixed
+        let (schema, schema_status) = match Schema::load(SCHEMA_FILE) {
+            Ok(schema) => (schema, Ok(String::new())),
+            Err(e) => (Schema::default(), Err(e.to_string())),
+        };
         Self {
             workbook_file: String::from(""),
             transaction_file: String::from(""),
-            sheet_names: vec![
-                String::from("Kontoutkskrift"),
-                String::from("Kategorier"),
-                String::from("Informasjon"),
-            ],
+            exchange_rate_file: String::from(""),
+            schema,
+            schema_status,
         }
     }
 }
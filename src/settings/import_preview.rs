@@ -0,0 +1,128 @@
+use eframe::egui;
+use egui::RichText;
+
+use crate::accounting::transaction::{Transaction, Transactions};
+use crate::excel::writing::PreviewRow;
+
+// Shows the rows an import would append and the transactions it skips, nothing is written until it is confirmed
+#[derive(Debug, Default)]
+pub struct ImportPreview {
+    pub rows: Vec<PreviewRow>,
+    pub skipped: Transactions,
+    // the sum of the rows in the reporting currency, or why it could not be converted
+    pub total: String,
+    // set when the user wants to write the rows
+    pub confirmed: bool,
+    // false when there is nothing to confirm
+    pub pending: bool,
+}
+
+impl ImportPreview {
+    pub fn init(&mut self, rows: Vec<PreviewRow>, skipped: Transactions, total: String) {
+        self.rows = rows;
+        self.skipped = skipped;
+        self.total = total;
+        self.confirmed = false;
+        self.pending = true;
+    }
+
+    pub fn reset(&mut self) {
+        *self = ImportPreview::default();
+    }
+
+    // the transactions to write, in the order they are appended
+    pub fn transactions(&self) -> Transactions {
+        self.rows
+            .iter()
+            .map(|row| row.transaction.clone())
+            .collect()
+    }
+}
+
+impl super::Window for ImportPreview {
+    fn name(&self) -> &'static str {
+        "Import Preview"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .collapsible(true)
+            .resizable(true)
+            .open(open)
+            .hscroll(true)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for ImportPreview {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if !self.pending {
+            ui.label("Nothing to preview");
+            return;
+        }
+        ui.heading(format!("Rows to append: {}", self.rows.len()));
+        egui::Grid::new("rows to append")
+            .striped(true)
+            .show(ui, |ui| {
+                header(ui, &["date", "text", "amount", "group", "income/expense"]);
+                for row in &self.rows {
+                    transaction_cells(ui, &row.transaction);
+                    ui.label(&row.group);
+                    ui.label(&row.income_expense);
+                    ui.end_row();
+                }
+            });
+        ui.label(format!("Sum: {}", self.total));
+
+        ui.add_space(8.0);
+        ui.collapsing(
+            format!("Skipped duplicates: {}", self.skipped.len()),
+            |ui| {
+                egui::Grid::new("skipped duplicates")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        header(ui, &["date", "text", "amount"]);
+                        for transaction in &self.skipped {
+                            transaction_cells(ui, transaction);
+                            ui.end_row();
+                        }
+                    });
+            },
+        );
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("write").clicked() {
+                self.confirmed = true;
+            }
+            if ui.button("cancel").clicked() {
+                self.reset();
+            }
+        });
+    }
+}
+
+fn header(ui: &mut egui::Ui, columns: &[&str]) {
+    for column in columns {
+        ui.label(RichText::new(*column).strong());
+    }
+    ui.end_row();
+}
+
+fn transaction_cells(ui: &mut egui::Ui, transaction: &Transaction) {
+    ui.label(transaction.accounting_date.to_string());
+    ui.label(&transaction.text);
+    // the original amount is what was paid in another currency
+    let mut amount = format!("{} {}", transaction.amount(), transaction.currency);
+    if !transaction.original_currency.is_empty() {
+        amount += &format!(
+            " ({} {})",
+            transaction.original_amount, transaction.original_currency
+        );
+    }
+    ui.label(amount.trim());
+}
@@ -1,5 +1,7 @@
 use std::error;
+use std::path::Path;
 
+use crate::error::{Error, Location};
 use crate::file;
 use file::lib;
 
@@ -23,8 +25,78 @@ pub fn same_path(path1: &str, path2: &str) -> Result<bool, Box<dyn error::Error>
     Ok(false)
 }
 
+// e.g. "in cell A5 of sheet Kontoutskrift in budget.xlsx" or "in column Beløp on line 3 of nordea.csv"
+fn place(location: &Location, file: &str) -> String {
+    match location {
+        Location::Cell { sheet, cell } => {
+            format!("in cell {} of sheet {} in {}", cell, sheet, file)
+        }
+        Location::Line { line, column } if column.is_empty() => {
+            format!("on line {} of {}", line, file)
+        }
+        Location::Line { line, column } => {
+            format!("in column {} on line {} of {}", column, line, file)
+        }
+        Location::Element { line, element } => {
+            format!("in element {} on line {} of {}", element, line, file)
+        }
+    }
+}
+
+// the errors of the workbook say where to look, the rest are shown as they are
 pub fn get_flash_error(error: Box<dyn error::Error>) -> String {
-    error.to_string()
+    let error = match error.downcast::<Error>() {
+        Ok(error) => error,
+        Err(error) => return error.to_string(),
+    };
+    let file = Path::new(error.file())
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_else(|| error.file());
+    match *error {
+        Error::MissingSheet { ref sheet, .. } => format!(
+            "The sheet '{}' is not in {}, check the sheet names in File information",
+            sheet, file
+        ),
+        Error::BadDate {
+            ref location,
+            ref value,
+            ..
+        } => format!("'{}' {} is not a date", value, place(location, file)),
+        Error::BadAmount {
+            ref location,
+            ref value,
+            ..
+        } => format!("'{}' {} is not an amount", value, place(location, file)),
+        Error::BadAccountNumber {
+            ref location,
+            ref value,
+            ..
+        } => format!(
+            "'{}' {} is not an account number",
+            value,
+            place(location, file)
+        ),
+        Error::BadValue {
+            ref location,
+            ref value,
+            ref expected,
+            ..
+        } => format!("'{}' {} is not {}", value, place(location, file), expected),
+        Error::BadFormat {
+            ref location,
+            ref reason,
+            ..
+        } => format!("Could not read {} at {}: {}", file, location, reason),
+        Error::NotStatement { ref format, .. } => {
+            format!("{} is not a {} statement", file, format)
+        }
+        Error::Io { ref reason, .. } => format!("Could not open {}: {}", file, reason),
+        Error::Write { ref reason, .. } => format!(
+            "Could not save {}, the workbook was not changed: {}",
+            file, reason
+        ),
+    }
 }
 
 #[cfg(test)]
@@ -41,5 +113,23 @@ mod test_settings_lib {
     fn test_get_flash_error() {
         let err: Box<dyn error::Error> = "test".into();
         assert_eq!(get_flash_error(err), "test");
+        let err: Box<dyn error::Error> = Box::new(Error::BadDate {
+            file: String::from("dir/budget.xlsx"),
+            location: Location::cell("Kontoutskrift", "A5"),
+            value: String::from("32.13.2022"),
+        });
+        assert_eq!(
+            get_flash_error(err),
+            "'32.13.2022' in cell A5 of sheet Kontoutskrift in budget.xlsx is not a date"
+        );
+        let err: Box<dyn error::Error> = Box::new(Error::bad_amount(
+            "dir/nordea.csv",
+            Location::line(3, "Beløp"),
+            "12,5o",
+        ));
+        assert_eq!(
+            get_flash_error(err),
+            "'12,5o' in column Beløp on line 3 of nordea.csv is not an amount"
+        );
     }
 }
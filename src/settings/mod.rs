@@ -2,6 +2,12 @@ pub mod lib;
 pub mod settings;
 pub mod file_information;
 pub mod workbook_information;
+pub mod duplicate_review;
+pub mod import_preview;
+pub mod new_workbook;
+pub mod workbook_check;
+pub mod category_conflicts;
+pub mod category_suggestions;
 
 pub use eframe::egui;
 
@@ -0,0 +1,147 @@
+use eframe::egui;
+use egui::{Color32, ComboBox, RichText};
+
+use crate::accounting;
+use crate::excel::template;
+
+// The language and accounts of a new workbook, it is made from the template when confirmed
+#[derive(Debug)]
+pub struct NewWorkbook {
+    pub language: String,
+    // name, number and currency as typed, checked when the workbook is made
+    pub accounts: Vec<(String, String, String)>,
+    // set when the user wants to make the workbook
+    pub confirmed: bool,
+}
+
+impl Default for NewWorkbook {
+    fn default() -> Self {
+        Self {
+            language: String::from(template::LANGUAGES[0]),
+            accounts: vec![(
+                String::new(),
+                String::new(),
+                String::from(accounting::currency::DEFAULT_CURRENCY),
+            )],
+            confirmed: false,
+        }
+    }
+}
+
+impl NewWorkbook {
+    // the accounts with a name, the number has to be a number
+    pub fn get_accounts(
+        &self,
+    ) -> Result<Vec<accounting::lib::Account>, Box<dyn std::error::Error>> {
+        let mut accounts = vec![];
+        for (name, number, currency) in &self.accounts {
+            if name.trim().is_empty() {
+                continue;
+            }
+            let number = number
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("the account number of '{}' is not a number", name.trim()))?;
+            accounts.push(accounting::lib::Account {
+                name: String::from(name.trim()),
+                number,
+                currency: accounting::currency::normalize(currency),
+            });
+        }
+        Ok(accounts)
+    }
+}
+
+impl super::Window for NewWorkbook {
+    fn name(&self) -> &'static str {
+        "New Workbook"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .collapsible(true)
+            .resizable(true)
+            .open(open)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for NewWorkbook {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ComboBox::from_label("Language")
+            .selected_text(&self.language)
+            .show_ui(ui, |ui| {
+                for language in template::LANGUAGES {
+                    ui.selectable_value(&mut self.language, String::from(language), language);
+                }
+            });
+        ui.add_space(8.0);
+
+        ui.heading("Accounts");
+        let mut remove = None;
+        egui::Grid::new("new accounts")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("name");
+                ui.label("number");
+                ui.label("currency");
+                ui.end_row();
+                for (i, (name, number, currency)) in self.accounts.iter_mut().enumerate() {
+                    ui.text_edit_singleline(name);
+                    ui.text_edit_singleline(number);
+                    ui.add(egui::TextEdit::singleline(currency).desired_width(40.0));
+                    if ui.button("remove").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = remove {
+            self.accounts.remove(i);
+        }
+        if ui.button("add account").clicked() {
+            self.accounts.push((
+                String::new(),
+                String::new(),
+                String::from(accounting::currency::DEFAULT_CURRENCY),
+            ));
+        }
+        if let Err(e) = self.get_accounts() {
+            ui.label(RichText::new(e.to_string()).color(Color32::from_rgb(255, 50, 50)));
+        }
+        ui.label("The workbook also gets a few categories to start from.");
+        ui.add_space(8.0);
+
+        if ui.button("create").clicked() {
+            self.confirmed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_new_workbook {
+    use super::*;
+
+    #[test]
+    fn test_get_accounts() {
+        let mut new_workbook = NewWorkbook::default();
+        assert!(new_workbook.get_accounts().unwrap().is_empty());
+        new_workbook.accounts = vec![
+            (
+                String::from(" Main "),
+                String::from("123"),
+                String::from("nok"),
+            ),
+            (String::new(), String::new(), String::new()),
+        ];
+        let accounts = new_workbook.get_accounts().unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].name, "Main");
+        assert_eq!(accounts[0].currency, "NOK");
+        new_workbook.accounts[0].1 = String::from("12a");
+        assert!(new_workbook.get_accounts().is_err());
+    }
+}
@@ -2,8 +2,14 @@ use eframe::egui;
 use egui::{color::*, ComboBox, RichText};
 use rfd;
 
+use super::category_conflicts::CategoryConflicts;
+use super::category_suggestions::CategorySuggestions;
+use super::duplicate_review::DuplicateReview;
 use super::file_information::FileInformation;
+use super::import_preview::ImportPreview;
 use super::lib;
+use super::new_workbook::NewWorkbook;
+use super::workbook_check::WorkbookCheck;
 use super::workbook_information::WorkbookInformation;
 use super::Window;
 use crate::accounting;
@@ -15,23 +21,50 @@ pub enum SettingsError {
     None,
     ValidFilename,
     ValidAccount,
+    ValidSchema,
 }
 
 pub struct Settings {
     // windows
     file_information_active: bool,
     workbook_information_active: bool,
+    duplicate_review_active: bool,
+    import_preview_active: bool,
+    new_workbook_active: bool,
+    workbook_check_active: bool,
+    category_conflicts_active: bool,
+    category_suggestions_active: bool,
     file_information: FileInformation,
     workbook_information: WorkbookInformation,
+    duplicate_review: DuplicateReview,
+    import_preview: ImportPreview,
+    new_workbook: NewWorkbook,
+    workbook_check: WorkbookCheck,
+    category_conflicts: CategoryConflicts,
+    category_suggestions: CategorySuggestions,
 
     // insert
     insert_cb: bool,
+    // how many days apart a similar transaction can be booked and still be a probable duplicate
+    fuzzy_days: u32,
+    // show the rows before they are written
+    preview_cb: bool,
+    // skip the rows of the export that cannot be read instead of stopping the import
+    lenient_cb: bool,
+    // the rows the last lenient import skipped
+    skipped_rows: Vec<accounting::bank::SkippedRow>,
+    // the balances of the last imported statement checked against its transactions and the workbook
+    balance_checks: Vec<(&'static str, accounting::bank::Reconciliation)>,
     // fill
     fill_cb: bool,
     fill_range: u32,
     fill_margin: u32,
     // regroup
     re_group_cb: bool,
+    // learn the groups of the empty rows from the rows that have one
+    learn_cb: bool,
+    // how sure, in percent, a learned group must be to be written without asking
+    learn_threshold: u32,
     // redate
     re_date_cb: bool,
     // date
@@ -44,6 +77,18 @@ pub struct Settings {
     date_capitalize: bool,
     // bank
     bank: accounting::bank::Bank,
+    // user defined import profiles, used instead of the bank when one is chosen
+    profiles: Vec<accounting::profile::ImportProfile>,
+    profile: Option<usize>,
+    // how many backups of the workbook are kept
+    backup_keep: u32,
+    // set when the workbook has been backed up for the current update, so undo takes back
+    // all the changes of an update at once
+    backed_up: bool,
+    // set when the fill, re group, learn and re date steps of an update wait for its import
+    steps_pending: bool,
+    // the currency of the amount column, other currencies are converted with the exchange rates
+    reporting_currency: String,
     // error/status
     flash_error: String,
     flash_ok: String,
@@ -56,19 +101,38 @@ impl Default for Settings {
         let path = String::new();
 
         let mut workbook_information = WorkbookInformation::default();
-        workbook_information.init(&path);
+        workbook_information.init(&path, &file_information.schema);
 
         Settings {
             file_information_active: false,
             workbook_information_active: false,
+            duplicate_review_active: false,
+            import_preview_active: false,
+            new_workbook_active: false,
+            workbook_check_active: false,
+            category_conflicts_active: false,
+            category_suggestions_active: false,
 
             file_information: file_information,
             workbook_information: workbook_information,
+            duplicate_review: DuplicateReview::default(),
+            import_preview: ImportPreview::default(),
+            new_workbook: NewWorkbook::default(),
+            workbook_check: WorkbookCheck::default(),
+            category_conflicts: CategoryConflicts::default(),
+            category_suggestions: CategorySuggestions::default(),
             insert_cb: false,
+            fuzzy_days: 3,
+            preview_cb: true,
+            lenient_cb: false,
+            skipped_rows: vec![],
+            balance_checks: vec![],
             fill_cb: false,
             fill_range: 10,
             fill_margin: 5,
             re_group_cb: false,
+            learn_cb: false,
+            learn_threshold: 90,
             re_date_cb: false,
             date_delimiter: vec![
                 (String::from(".")),
@@ -81,7 +145,13 @@ impl Default for Settings {
             date_language: vec![String::from("norsk"), String::from("english")],
             date_language_active: String::from("norsk"),
             date_capitalize: false,
-            bank: accounting::bank::Bank::SBanken,
+            bank: accounting::bank::Bank::default(),
+            profiles: accounting::profile::load_profiles(accounting::profile::PROFILE_DIR),
+            profile: None,
+            backup_keep: file::backup::DEFAULT_KEEP as u32,
+            backed_up: false,
+            steps_pending: false,
+            reporting_currency: String::from(accounting::currency::DEFAULT_CURRENCY),
             flash_error: String::new(),
             flash_ok: String::new(),
             error: SettingsError::None,
@@ -92,9 +162,13 @@ impl Default for Settings {
 impl Settings {
     pub fn update(&mut self, ctx: &egui::Context) {
         self.error = SettingsError::None;
+        if self.file_information.schema.validate().is_err() {
+            self.error = SettingsError::ValidSchema;
+        }
         // saves the current path to compare if the new has changed
         let prev_workbook_path = String::from(&self.file_information.workbook_file);
         let prev_transaction_path = String::from(&self.file_information.transaction_file);
+        let prev_schema = self.file_information.schema.clone();
 
         egui::CentralPanel::default().show(ctx, |ui| {
             // <----- INPUT ----->
@@ -117,7 +191,7 @@ impl Settings {
             if self.insert_cb {
             ui.horizontal(|ui| {
                 if ui.button("transaction file").clicked() {
-                    match rfd::FileDialog::new().add_filter("transaction_file", &["xlsx"]).pick_file() {
+                    match rfd::FileDialog::new().add_filter("transaction_file", self.get_importer().extensions()).pick_file() {
                         Some(path) => match path.to_str() {
                             Some(p) => self.file_information.transaction_file= String::from(p),
                             _ => (),
@@ -130,6 +204,24 @@ impl Settings {
                     self.error = SettingsError::ValidFilename;
                 };
             });
+            // the exchange rates are only needed for accounts in other currencies
+            ui.horizontal(|ui| {
+                if ui.button("exchange rate file").clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("exchange_rate_file", &["csv"])
+                        .pick_file()
+                    {
+                        if let Some(p) = path.to_str() {
+                            self.file_information.exchange_rate_file = String::from(p);
+                        }
+                    }
+                }
+                ui.label(&self.file_information.exchange_rate_file);
+            });
+            ui.horizontal(|ui| {
+                ui.label("reporting currency");
+                ui.text_edit_singleline(&mut self.reporting_currency);
+            });
             }
             ui.add_space(8.0);
 
@@ -145,7 +237,14 @@ impl Settings {
                         );
                         // update the account information
                         match path {
-                            Ok(path) => self.workbook_information.update_all_workbook_information(&path),
+                            Ok(path) => {
+                                if let Err(e) = self
+                                    .workbook_information
+                                    .update_all_workbook_information(&path, &self.file_information.schema)
+                                {
+                                    self.flash_error = lib::get_flash_error(e);
+                                }
+                            }
                             Err(_) => {
                                 self.error = SettingsError::ValidFilename;
                                 self.workbook_information.reset_accounts()
@@ -160,6 +259,23 @@ impl Settings {
                     self.file_information_active = true;
                 };
             });
+
+            ui.horizontal(|ui| {
+                if ui.button("Check workbook").clicked() {
+                    self.workbook_check_active = true;
+                    self.workbook_check.recheck = true;
+                };
+                if ui.button("Category conflicts").clicked() {
+                    self.category_conflicts_active = true;
+                    self.category_conflicts.recheck = true;
+                };
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("New workbook").clicked() {
+                    self.new_workbook_active = true;
+                };
+            });
             // adding space between different sections
             ui.add_space(8.0);
 
@@ -180,6 +296,20 @@ impl Settings {
                             }
                         })
                 });
+                // combobox, choose a user defined profile
+                ui.vertical(|ui| {
+                    ComboBox::from_label("Profile")
+                        .selected_text(match self.profile {
+                            Some(i) => self.profiles[i].name.clone(),
+                            None => String::from("none"),
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.profile, None, "none");
+                            for (i, profile) in self.profiles.iter().enumerate() {
+                                ui.selectable_value(&mut self.profile, Some(i), &profile.name);
+                            }
+                        })
+                });
                 // combobox, choose account
                 ui.vertical(|ui| {
                     ComboBox::from_label("Account")
@@ -202,7 +332,14 @@ impl Settings {
                     );
                     // update the account information
                     match path {
-                        Ok(path) => self.workbook_information.update_all_workbook_information(&path),
+                        Ok(path) => {
+                                if let Err(e) = self
+                                    .workbook_information
+                                    .update_all_workbook_information(&path, &self.file_information.schema)
+                                {
+                                    self.flash_error = lib::get_flash_error(e);
+                                }
+                            }
                         Err(_) => {
                             self.error = SettingsError::ValidFilename;
                             self.workbook_information.reset_accounts()
@@ -272,6 +409,19 @@ impl Settings {
             // <----- CHECKBOX ----->
             ui.checkbox(&mut self.insert_cb, "insert");
             ui.label("When enabled, the program will insert new data from the file location");
+            if self.insert_cb {
+                ui.add(egui::Slider::new(&mut self.fuzzy_days, 0..=10).text("days"));
+                ui.label("Similar transactions with the same amount this many days apart are reviewed before they are inserted");
+                ui.checkbox(&mut self.preview_cb, "preview");
+                ui.label("When enabled, the rows and their groups are shown before anything is written");
+                let importer = self.get_importer();
+                let (skips_rows, importer_name) = (importer.skips_rows(), importer.name().to_string());
+                ui.add_enabled(skips_rows, egui::Checkbox::new(&mut self.lenient_cb, "skip bad rows"));
+                ui.label("When enabled, rows of the file that cannot be read are skipped and listed after the update");
+                if !skips_rows {
+                    ui.label(format!("{} files cannot skip a row, a row that cannot be read stops the update", importer_name));
+                }
+            };
             ui.add_space(8.0);
 
             ui.checkbox(&mut self.fill_cb, "fill");
@@ -292,10 +442,22 @@ impl Settings {
             }
             ui.add_space(8.0);
 
+            ui.checkbox(&mut self.learn_cb, "learn");
+            ui.label("When enabled, the rows the categories give no group get the group of similar rows");
+            if self.learn_cb {
+                ui.add(egui::Slider::new(&mut self.learn_threshold, 50..=100).text("% sure"));
+                ui.label("Groups at least this sure are written, the others are shown as suggestions");
+            }
+            ui.add_space(8.0);
+
             ui.checkbox(&mut self.re_date_cb, "re date");
             ui.label("When enabled, the program will change the date format");
             ui.add_space(8.0);
 
+            ui.add(egui::Slider::new(&mut self.backup_keep, 1..=50).text("backups"));
+            ui.label(format!("A copy of the workbook is stored in the '{}' folder before every change, specify how many to keep", file::backup::BACKUP_DIR));
+            ui.add_space(8.0);
+
             // <----- FLASH MESSAGE ----->
             ui.vertical_centered(|ui| {
                 let mut error = &String::from(self.get_error());
@@ -316,18 +478,53 @@ impl Settings {
                 }
             });
 
+            // <----- SKIPPED ROWS ----->
+            if !self.skipped_rows.is_empty() {
+                ui.collapsing(
+                    format!("Skipped rows: {}", self.skipped_rows.len()),
+                    |ui| {
+                        for row in &self.skipped_rows {
+                            ui.label(
+                                RichText::new(row.to_string())
+                                    .color(Color32::from_rgb(255, 200, 50)),
+                            );
+                        }
+                    },
+                );
+            }
+
+            // <----- BALANCES ----->
+            for (name, check) in &self.balance_checks {
+                let color = if check.is_ok() {
+                    Color32::from_rgb(50, 255, 50)
+                } else {
+                    Color32::from_rgb(255, 200, 50)
+                };
+                ui.label(RichText::new(format!("{}: {}", name, check)).color(color));
+            }
+
             // <----- RUN BUTTON ----->
             ui.vertical_centered(|ui| {
                 if ui.button("update").clicked() {
                     // reset flash ok if update button clicked
                     self.flash_ok = String::new();
+                    self.skipped_rows = vec![];
+                    self.balance_checks = vec![];
+                    self.backed_up = false;
                     if self.error == SettingsError::None {
                         // update the account information
                         let path = file::lib::valid_file(
                             &self.file_information.workbook_file,
                         );
                         match path {
-                            Ok(path) => self.workbook_information.update_categories(&path),
+                            Ok(path) => {
+                                if let Err(e) = self
+                                    .workbook_information
+                                    .update_categories(&path, &self.file_information.schema)
+                                {
+                                    self.flash_error = lib::get_flash_error(e);
+                                }
+                            }
                             Err(_) => {
                                 self.error = SettingsError::ValidFilename;
                                 self.workbook_information.reset_accounts()
@@ -344,20 +541,30 @@ impl Settings {
                             match workbook_path {
                                 Ok(wp) => match transaction_path {
                                     Ok(tp) => {
-                                        match excel::writing::write_to_workbook(
+                                        match excel::writing::prepare_import(
                                             &wp,
                                             &tp,
-                                            self.bank,
-                                            &self.workbook_information.active_account.name,
-                                            &self.workbook_information.categories,
-                                            &self.date_delimiter_active,
-                                            &self.date_month_style_active,
-                                            &self.date_language_active,
-                                            &self.date_capitalize,
+                                            self.get_importer(),
+                                            &self.workbook_information.active_account,
+                                            &self.file_information.schema,
+                                            self.fuzzy_days as i64,
+                                            self.lenient_cb,
                                         ) {
-                                            Ok(_) => {
-                                                self.flash_ok =
-                                                    String::from("Successfully written to workbook")
+                                            // nothing to review, write it at once
+                                            Ok(pending) if pending.suspects.is_empty() => {
+                                                self.balance_checks = pending.balance_checks();
+                                                self.skipped_rows = pending.skipped_rows;
+                                                self.insert_transactions(&wp, pending.transactions, pending.duplicates)
+                                            }
+                                            Ok(pending) => {
+                                                self.balance_checks = pending.balance_checks();
+                                                self.skipped_rows = pending.skipped_rows.clone();
+                                                self.flash_ok = format!(
+                                                    "Found {} probable duplicates, review them before they are written",
+                                                    pending.suspects.len()
+                                                );
+                                                self.duplicate_review.init(pending);
+                                                self.duplicate_review_active = true;
                                             }
                                             Err(e) => self.flash_error = lib::get_flash_error(e),
                                         }
@@ -368,67 +575,54 @@ impl Settings {
                             };
                         };
 
-                        // if fille checkbox is set
-                        if self.fill_cb {
-                            let workbook_path = file::lib::valid_file(
-                                &self.file_information.workbook_file,
-                            );
-                            match workbook_path {
-                                Ok(wp) => match excel::writing::fill_empty_rows(
-                                    &wp,
-                                    self.fill_range,
-                                    self.fill_margin,
-                                ) {
-                                    Ok(_) => {
-                                        self.flash_ok =
-                                            String::from("Successfully filled empty groups")
-                                    }
-                                    Err(e) => self.flash_error = lib::get_flash_error(e),
-                                },
-                                Err(e) => self.flash_error = lib::get_flash_error(e),
-                            }
+                        // the other steps change the rows of the workbook, so they wait for
+                        // an import that is previewed or reviewed to be written first
+                        if self.import_pending() {
+                            self.steps_pending = true;
+                        } else {
+                            self.run_steps();
                         }
+                    };
+                };
 
-                        if self.re_group_cb {
-                            let workbook_path = file::lib::valid_file(
-                                &self.file_information.workbook_file,
-                            );
-                            match workbook_path {
-                                Ok(wp) => match excel::writing::re_group(
-                                    &wp,
-                                    &self.workbook_information.categories,
-                                ) {
-                                    Ok(_) => {
-                                        self.flash_ok = String::from("Successfully re-grouped")
-                                    }
-                                    Err(e) => self.flash_error = lib::get_flash_error(e),
-                                },
-                                Err(e) => self.flash_error = lib::get_flash_error(e),
-                            }
+                // puts back the workbook as it was before the last change
+                if ui.button("Undo last update").clicked() {
+                    self.flash_ok = String::new();
+                    match file::lib::valid_file(&self.file_information.workbook_file)
+                        .and_then(|wp| file::backup::restore_latest(&wp))
+                    {
+                        Ok(backup) => {
+                            self.flash_ok = format!("Restored the workbook from {}", backup.display());
+                            // the accounts and categories may have changed
+                            self.workbook_information.updated = false;
                         }
+                        Err(e) => self.flash_error = lib::get_flash_error(e),
+                    }
+                }
 
-                        if self.re_date_cb {
-                            let workbook_path = file::lib::valid_file(
-                                &self.file_information.workbook_file,
-                            );
-                            match workbook_path {
-                                Ok(wp) => match excel::writing::re_date(
+                // exports the active account of the workbook to a qif file
+                if ui.button("export qif").clicked() {
+                    self.flash_ok = String::new();
+                    match file::lib::valid_file(&self.file_information.workbook_file) {
+                        Ok(wp) => {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("qif_file", &["qif"])
+                                .save_file()
+                            {
+                                match accounting::qif::export(
                                     &wp,
-                                    &self.date_delimiter_active,
-                                    &self.date_month_style_active,
-                                    &self.date_language_active,
-                                    &self.date_capitalize,
+                                    &self.file_information.schema,
+                                    &path.to_string_lossy(),
+                                    &self.workbook_information.active_account.name,
                                 ) {
-                                    Ok(_) => {
-                                        self.flash_ok = String::from("Successfully re-dated")
-                                    }
+                                    Ok(_) => self.flash_ok = String::from("Successfully exported to qif"),
                                     Err(e) => self.flash_error = lib::get_flash_error(e),
-                                },
-                                Err(e) => self.flash_error = lib::get_flash_error(e),
+                                }
                             }
                         }
-                    };
-                };
+                        Err(e) => self.flash_error = lib::get_flash_error(e),
+                    }
+                }
             });
         });
 
@@ -441,7 +635,166 @@ impl Settings {
         self.file_information
             .show(ctx, &mut self.file_information_active);
 
+        // show the probable duplicates of the last import
+        self.duplicate_review
+            .show(ctx, &mut self.duplicate_review_active);
+
+        // show the rows of the last import
+        self.import_preview
+            .show(ctx, &mut self.import_preview_active);
+
+        // show new workbook window
+        self.new_workbook.show(ctx, &mut self.new_workbook_active);
+
+        // show the problems found in the workbook
+        self.workbook_check
+            .show(ctx, &mut self.workbook_check_active);
+
+        // show the learned groups that were not sure enough to be written
+        self.category_suggestions
+            .show(ctx, &mut self.category_suggestions_active);
+
+        // show the transactions and rules the categories disagree on
+        self.category_conflicts
+            .show(ctx, &mut self.category_conflicts_active);
+
         // <----- CHECKS ----->
+        // checks the layout of the workbook against the schema
+        if self.workbook_check.recheck {
+            self.workbook_check.recheck = false;
+            match file::lib::valid_file(&self.file_information.workbook_file).and_then(|path| {
+                excel::validate::validate_workbook(&path, &self.file_information.schema)
+            }) {
+                Ok(problems) => self.workbook_check.init(problems),
+                Err(e) => {
+                    self.workbook_check_active = false;
+                    self.flash_error = lib::get_flash_error(e);
+                }
+            }
+        }
+
+        // finds the transactions of the workbook more than one group has a rule for, the
+        // categories and rules are read again so edits since the last update are checked
+        if self.category_conflicts.recheck {
+            self.category_conflicts.recheck = false;
+            let schema = &self.file_information.schema;
+            let workbook_information = &mut self.workbook_information;
+            match file::lib::valid_file(&self.file_information.workbook_file).and_then(|path| {
+                workbook_information.update_categories(&path, schema)?;
+                excel::reading::get_workbook_transactions(&path, schema)
+            }) {
+                Ok(transactions) => {
+                    let categories = &self.workbook_information.categories;
+                    self.category_conflicts.init(
+                        excel::conflicts::find_conflicts(&transactions, categories),
+                        excel::conflicts::find_shadowed(categories),
+                        schema.first_row(),
+                    );
+                }
+                Err(e) => {
+                    self.category_conflicts_active = false;
+                    self.flash_error = lib::get_flash_error(e);
+                }
+            }
+        }
+
+        // makes the new workbook and uses it
+        if self.new_workbook.confirmed {
+            self.new_workbook.confirmed = false;
+            self.flash_ok = String::new();
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("workbook_file", &["xlsx"])
+                .save_file()
+            {
+                let path = path.to_string_lossy().to_string();
+                match self.new_workbook.get_accounts().and_then(|accounts| {
+                    excel::template::new_workbook(&path, &self.new_workbook.language, &accounts)
+                }) {
+                    Ok(schema) => {
+                        self.flash_ok = format!(
+                            "Created {}, save the schema in File information to keep it",
+                            path
+                        );
+                        self.file_information.workbook_file = path;
+                        self.file_information.schema = schema;
+                        self.workbook_information.updated = false;
+                        self.new_workbook_active = false;
+                    }
+                    Err(e) => self.flash_error = lib::get_flash_error(e),
+                }
+            }
+        }
+
+        // the accounts and categories are read again with the new schema
+        if prev_schema != self.file_information.schema {
+            self.workbook_information.updated = false;
+        }
+
+        // inserts the reviewed import, the workbook may have changed since it was read
+        if self.duplicate_review.confirmed {
+            if let Some(pending) = self.duplicate_review.pending.take() {
+                let transactions = pending.accepted(&self.duplicate_review.accepted);
+                let skipped = pending.skipped(&self.duplicate_review.accepted);
+                match file::lib::valid_file(&self.file_information.workbook_file) {
+                    Ok(wp) => self.insert_transactions(&wp, transactions, skipped),
+                    Err(e) => self.flash_error = lib::get_flash_error(e),
+                }
+            }
+            self.duplicate_review.reset();
+        }
+        // closing the window or clicking cancel drops the import
+        if !self.duplicate_review_active || self.duplicate_review.pending.is_none() {
+            self.duplicate_review.reset();
+            self.duplicate_review_active = false;
+        }
+
+        // writes the accepted suggestions, it is undone on its own
+        if self.category_suggestions.confirmed {
+            let accepted = self.category_suggestions.accepted();
+            self.backed_up = false;
+            match file::lib::valid_file(&self.file_information.workbook_file).and_then(|wp| {
+                excel::writing::apply_suggestions(
+                    &wp,
+                    &self.file_information.schema,
+                    &accepted,
+                    &mut || backup_once(&mut self.backed_up, &wp, self.backup_keep),
+                )
+            }) {
+                Ok(_) => {
+                    self.flash_ok =
+                        format!("Successfully wrote {} suggested groups", accepted.len())
+                }
+                Err(e) => self.flash_error = lib::get_flash_error(e),
+            }
+            self.category_suggestions.reset();
+        }
+        // closing the window or clicking cancel drops the suggestions
+        if !self.category_suggestions_active || self.category_suggestions.suggestions.is_empty() {
+            self.category_suggestions.reset();
+            self.category_suggestions_active = false;
+        }
+
+        // writes the previewed rows
+        if self.import_preview.confirmed {
+            match file::lib::valid_file(&self.file_information.workbook_file)
+                .and_then(|wp| self.write_transactions(&wp, self.import_preview.transactions()))
+            {
+                Ok(_) => self.flash_ok = String::from("Successfully written to workbook"),
+                Err(e) => self.flash_error = lib::get_flash_error(e),
+            }
+            self.import_preview.reset();
+        }
+        if !self.import_preview_active || !self.import_preview.pending {
+            self.import_preview.reset();
+            self.import_preview_active = false;
+        }
+
+        // the rest of the update runs when its import has been written or dropped
+        if self.steps_pending && !self.import_pending() {
+            self.steps_pending = false;
+            self.run_steps();
+        }
+
         // if the path has been updated
         match lib::same_path(&prev_workbook_path, &self.file_information.workbook_file) {
             Ok(value) => {
@@ -474,26 +827,208 @@ impl Settings {
         };
     }
 
+    // fills, re groups, learns and re dates the rows of the workbook as checked
+    fn run_steps(&mut self) {
+        // if fille checkbox is set
+        if self.fill_cb {
+            let workbook_path = file::lib::valid_file(&self.file_information.workbook_file);
+            match workbook_path {
+                Ok(wp) => match excel::writing::fill_empty_rows(
+                    &wp,
+                    &self.file_information.schema,
+                    self.fill_range,
+                    self.fill_margin,
+                    &mut || backup_once(&mut self.backed_up, &wp, self.backup_keep),
+                ) {
+                    Ok(_) => self.flash_ok = String::from("Successfully filled empty groups"),
+                    Err(e) => self.flash_error = lib::get_flash_error(e),
+                },
+                Err(e) => self.flash_error = lib::get_flash_error(e),
+            }
+        }
+
+        if self.re_group_cb {
+            let workbook_path = file::lib::valid_file(&self.file_information.workbook_file);
+            match workbook_path {
+                Ok(wp) => match excel::writing::re_group(
+                    &wp,
+                    &self.file_information.schema,
+                    &self.workbook_information.categories,
+                    &mut || backup_once(&mut self.backed_up, &wp, self.backup_keep),
+                ) {
+                    Ok(_) => self.flash_ok = String::from("Successfully re-grouped"),
+                    Err(e) => self.flash_error = lib::get_flash_error(e),
+                },
+                Err(e) => self.flash_error = lib::get_flash_error(e),
+            }
+        }
+
+        if self.learn_cb {
+            let workbook_path = file::lib::valid_file(&self.file_information.workbook_file);
+            match workbook_path {
+                Ok(wp) => match excel::writing::learn_groups(
+                    &wp,
+                    &self.file_information.schema,
+                    &self.workbook_information.categories,
+                    self.learn_threshold as f64 / 100.0,
+                    &mut || backup_once(&mut self.backed_up, &wp, self.backup_keep),
+                ) {
+                    Ok(learned) => {
+                        self.flash_ok = format!(
+                            "Learned the group of {} rows, {} suggestions to review",
+                            learned.applied.len(),
+                            learned.suggestions.len()
+                        );
+                        if !learned.suggestions.is_empty() {
+                            self.category_suggestions.init(learned.suggestions);
+                            self.category_suggestions_active = true;
+                        }
+                    }
+                    Err(e) => self.flash_error = lib::get_flash_error(e),
+                },
+                Err(e) => self.flash_error = lib::get_flash_error(e),
+            }
+        }
+
+        if self.re_date_cb {
+            let workbook_path = file::lib::valid_file(&self.file_information.workbook_file);
+            match workbook_path {
+                Ok(wp) => match excel::writing::re_date(
+                    &wp,
+                    &self.file_information.schema,
+                    &self.date_delimiter_active,
+                    &self.date_month_style_active,
+                    &self.date_language_active,
+                    &self.date_capitalize,
+                    &mut || backup_once(&mut self.backed_up, &wp, self.backup_keep),
+                ) {
+                    Ok(_) => self.flash_ok = String::from("Successfully re-dated"),
+                    Err(e) => self.flash_error = lib::get_flash_error(e),
+                },
+                Err(e) => self.flash_error = lib::get_flash_error(e),
+            }
+        }
+    }
+
+    // true while an import waits in the duplicate review or the preview
+    fn import_pending(&self) -> bool {
+        self.duplicate_review.pending.is_some() || self.import_preview.pending
+    }
+
+    // shows the rows in the preview window if preview is enabled, else writes them
+    fn insert_transactions(
+        &mut self,
+        workbook_path: &str,
+        transactions: accounting::transaction::Transactions,
+        skipped: accounting::transaction::Transactions,
+    ) {
+        if self.preview_cb {
+            let rows =
+                excel::writing::preview(&transactions, &self.workbook_information.categories);
+            self.flash_ok = format!(
+                "{} rows to append and {} skipped, check the preview before it is written",
+                rows.len(),
+                skipped.len()
+            );
+            let total = match self
+                .get_exchange_rates()
+                .and_then(|rates| rates.sum(&transactions))
+            {
+                Ok(sum) => format!("{} {}", sum, self.reporting_currency),
+                Err(e) => lib::get_flash_error(e),
+            };
+            self.import_preview.init(rows, skipped, total);
+            self.import_preview_active = true;
+            return;
+        }
+        match self.write_transactions(workbook_path, transactions) {
+            Ok(_) => self.flash_ok = String::from("Successfully written to workbook"),
+            Err(e) => self.flash_error = lib::get_flash_error(e),
+        }
+    }
+
+    // writes the transactions with the chosen categories, date format and exchange rates
+    fn write_transactions(
+        &mut self,
+        workbook_path: &str,
+        transactions: accounting::transaction::Transactions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rates = self.get_exchange_rates()?;
+        excel::writing::write(
+            workbook_path,
+            transactions,
+            &self.workbook_information.categories,
+            &excel::writing::WriteOptions {
+                schema: &self.file_information.schema,
+                rates: &rates,
+                date_delimiter: &self.date_delimiter_active,
+                date_month_style: &self.date_month_style_active,
+                date_language: &self.date_language_active,
+                date_capitalize: self.date_capitalize,
+            },
+            &mut || backup_once(&mut self.backed_up, workbook_path, self.backup_keep),
+        )
+    }
+
+    // no exchange rate file means that every account is in the reporting currency
+    fn get_exchange_rates(
+        &self,
+    ) -> Result<accounting::currency::ExchangeRates, Box<dyn std::error::Error>> {
+        if self.file_information.exchange_rate_file.is_empty() {
+            return Ok(accounting::currency::ExchangeRates::new(
+                &self.reporting_currency,
+            ));
+        }
+        accounting::currency::ExchangeRates::from_csv(
+            &self.file_information.exchange_rate_file,
+            &self.reporting_currency,
+        )
+    }
+
+    // the chosen profile if there is one, else the chosen bank
+    fn get_importer(&self) -> &dyn accounting::bank::BankImporter {
+        match self.profile {
+            Some(i) => &self.profiles[i],
+            None => self.bank.importer(),
+        }
+    }
+
     pub fn get_error(&self) -> &str {
         match self.error {
             SettingsError::ValidFilename => "That file do not exist in that directory",
             SettingsError::ValidAccount => "Need to choose an account",
+            SettingsError::ValidSchema => "The workbook schema in File information is not valid",
             SettingsError::None => "",
         }
     }
 }
 
+// copies the workbook to the backup folder before the first change of an update
+fn backup_once(
+    backed_up: &mut bool,
+    workbook_path: &str,
+    keep: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !*backed_up {
+        file::backup::backup(workbook_path, keep as usize)?;
+        *backed_up = true;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test_workbook_information {
     use super::*;
+    use crate::excel::schema::Schema;
 
     #[test]
     fn test_reset_accounts() {
         let mut info = WorkbookInformation::default();
-        info.init("");
+        info.init("", &Schema::default());
         info.accounts = vec![accounting::lib::Account {
             name: String::from("name"),
             number: 0,
+            currency: String::from("NOK"),
         }];
         info.reset_accounts();
         assert_eq!(info.accounts.len(), 0);
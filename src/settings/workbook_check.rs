@@ -0,0 +1,73 @@
+use eframe::egui;
+use egui::{Color32, RichText};
+
+use crate::excel::validate::Problem;
+
+// The problems the validator found in the workbook, with the sheet and cell of each
+#[derive(Debug, Default)]
+pub struct WorkbookCheck {
+    pub problems: Vec<Problem>,
+    // false until the workbook has been checked
+    pub checked: bool,
+    // set when the user wants to check the workbook again
+    pub recheck: bool,
+}
+
+impl WorkbookCheck {
+    pub fn init(&mut self, problems: Vec<Problem>) {
+        self.problems = problems;
+        self.checked = true;
+        self.recheck = false;
+    }
+}
+
+impl super::Window for WorkbookCheck {
+    fn name(&self) -> &'static str {
+        "Workbook Check"
+    }
+
+    fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(self.name())
+            .collapsible(true)
+            .resizable(true)
+            .open(open)
+            .hscroll(true)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                use super::View as _;
+                self.ui(ui);
+            });
+    }
+}
+
+impl super::View for WorkbookCheck {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        if !self.checked {
+            ui.label("The workbook has not been checked");
+        } else if self.problems.is_empty() {
+            ui.label(RichText::new("No problems found").color(Color32::from_rgb(50, 255, 50)));
+        } else {
+            ui.heading(format!("Problems: {}", self.problems.len()));
+            egui::Grid::new("workbook problems")
+                .striped(true)
+                .show(ui, |ui| {
+                    for column in ["sheet", "cell", "problem"] {
+                        ui.label(RichText::new(column).strong());
+                    }
+                    ui.end_row();
+                    for problem in &self.problems {
+                        ui.label(&problem.sheet);
+                        ui.label(&problem.cell);
+                        ui.label(
+                            RichText::new(&problem.message).color(Color32::from_rgb(255, 50, 50)),
+                        );
+                        ui.end_row();
+                    }
+                });
+        }
+        ui.add_space(8.0);
+        if ui.button("check again").clicked() {
+            self.recheck = true;
+        }
+    }
+}
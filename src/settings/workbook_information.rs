@@ -1,5 +1,6 @@
 use crate::accounting;
 use crate::excel;
+use crate::excel::schema::Schema;
 use eframe::egui;
 use egui::{Color32, RichText};
 
@@ -104,21 +105,23 @@ impl super::View for WorkbookInformation {
                 }
             });
         });
+        ui.collapsing(format!("rules from {}", excel::rules::RULES_FILE), |ui| {
+            if self.categories.rules.is_empty() {
+                ui.label("No rules, they are tried before the categories sheet");
+            }
+            for rule in &self.categories.rules {
+                ui.label(rule.to_string());
+            }
+        });
     }
 }
 
 impl Default for WorkbookInformation {
     fn default() -> Self {
         let accs = vec![];
-        let active_account = accounting::lib::Account {
-            name: String::new(),
-            number: 0,
-        };
+        let active_account = accounting::lib::Account::default();
 
-        let categories = excel::reading::Categories {
-            from_text: vec![],
-            from_type: vec![],
-        };
+        let categories = excel::reading::Categories::default();
 
         Self {
             accounts: accs,
@@ -130,28 +133,41 @@ impl Default for WorkbookInformation {
 }
 
 impl WorkbookInformation {
-    pub fn init(&mut self, path: &str) {
-        self.update_all_workbook_information(path)
+    pub fn init(&mut self, path: &str, schema: &Schema) {
+        // the rules file is checked again on the first update
+        let _ = self.update_all_workbook_information(path, schema);
     }
 
-    pub fn update_all_workbook_information(&mut self, path: &str) {
-        self.update_accounts(path);
-        self.update_categories(path);
+    pub fn update_all_workbook_information(
+        &mut self,
+        path: &str,
+        schema: &Schema,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.update_accounts(path, schema);
         self.updated = true;
+        self.update_categories(path, schema)
     }
 
-    pub fn update_categories(&mut self, path: &str) {
+    // the categories are read even if the rules file is not valid, the error tells why
+    // there are no rules
+    pub fn update_categories(
+        &mut self,
+        path: &str,
+        schema: &Schema,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let mut categories = excel::reading::Categories::new(vec![], vec![]);
-        match excel::reading::get_categories(path) {
+        match excel::reading::get_categories(path, schema) {
             Ok(cat) => categories = cat,
             Err(_) => (),
         };
-        self.categories = categories;
+        let rules = excel::rules::load_rules(excel::rules::RULES_FILE);
+        self.categories = categories.with_rules(rules.as_ref().cloned().unwrap_or_default());
+        rules.map(|_| ())
     }
 
-    fn update_accounts(&mut self, path: &str) {
+    fn update_accounts(&mut self, path: &str, schema: &Schema) {
         let mut accounts = vec![];
-        match excel::reading::get_accounts(path) {
+        match excel::reading::get_accounts(path, schema) {
             Ok(accs) => accounts = accs,
             Err(_) => (),
         };
@@ -162,20 +178,14 @@ impl WorkbookInformation {
     }
 
     pub fn reset_accounts(&mut self) {
-        self.active_account = accounting::lib::Account {
-            name: String::new(),
-            number: 0,
-        };
+        self.active_account = accounting::lib::Account::default();
         self.accounts = vec![];
     }
 
     pub fn get_accounts(&self) -> Vec<accounting::lib::Account> {
         let mut res = vec![];
         for account in &self.accounts {
-            res.push(accounting::lib::Account {
-                name: account.name.clone(),
-                number: account.number.clone(),
-            })
+            res.push(accounting::lib::Account::clone(account))
         }
         res
     }